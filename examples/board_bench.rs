@@ -0,0 +1,81 @@
+//! Compares `Board` with `BitBoard` by counting every way a game can go,
+//! which is mostly making moves, taking them back and checking for wins.
+//!
+//! Run with `cargo run --release --example board_bench`
+
+use std::time::{Duration, Instant};
+
+use x_and_o::{BitBoard, Board, Player, PlayerSymbol};
+
+fn main() {
+    // (width, height, win length, moves deep)
+    let cases = [(3, 3, 3, 9), (4, 4, 4, 6), (5, 5, 4, 5)];
+    for (width, height, win_length, depth) in cases {
+        println!(
+            "{}x{}, {} in a row, {} moves deep",
+            width, height, win_length, depth
+        );
+
+        let mut board = Board::with_size(width, height, win_length).expect("valid size");
+        let cross = Player::new(PlayerSymbol::Cross, 1);
+        let nought = Player::new(PlayerSymbol::Nought, 2);
+        let (count, elapsed) = time(|| count_board(&mut board, cross, nought, depth));
+        report("Board", count, elapsed);
+
+        let mut bitboard = BitBoard::new(width, height, win_length).expect("fits a bitboard");
+        let (count, elapsed) = time(|| count_bitboard(&mut bitboard, PlayerSymbol::Cross, depth));
+        report("BitBoard", count, elapsed);
+        println!();
+    }
+}
+
+/// Runs `f`, timing it
+fn time(f: impl FnOnce() -> u64) -> (u64, Duration) {
+    let start = Instant::now();
+    let count = f();
+    (count, start.elapsed())
+}
+
+fn report(name: &str, count: u64, elapsed: Duration) {
+    let per_second = count as f64 / elapsed.as_secs_f64();
+    println!(
+        "  {:<9} {:>10} games in {:>8.1?} ({:.1}M per second)",
+        name,
+        count,
+        elapsed,
+        per_second / 1e6
+    );
+}
+
+/// Counts the games from `board` that end within `depth` moves,
+/// plus the unfinished ones cut off at `depth`
+fn count_board(board: &mut Board, player: Player, opponent: Player, depth: u8) -> u64 {
+    if depth == 0 || board.is_game_over() {
+        return 1;
+    }
+    let mut count = 0;
+    for (x, y) in board.empty_cells() {
+        board.set(x, y, player).expect("empty cell");
+        count += count_board(board, opponent, player, depth - 1);
+        board.clear(x, y).expect("cell on the board");
+    }
+    count
+}
+
+/// Same as `count_board`, on a bitboard
+fn count_bitboard(board: &mut BitBoard, symbol: PlayerSymbol, depth: u8) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let mut count = 0;
+    for cell in board.moves() {
+        board.make_move(cell, symbol);
+        count += if board.wins_through(cell, symbol) || board.is_full() {
+            1
+        } else {
+            count_bitboard(board, symbol.opposite(), depth - 1)
+        };
+        board.unmake_move(cell, symbol);
+    }
+    count
+}
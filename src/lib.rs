@@ -0,0 +1,39 @@
+//! Noughts and crosses on boards of any size, with computer opponents,
+//! saved games and game records.
+//!
+//! Everything needed to play is re-exported from the crate root;
+//! the modules the items live in are private so they can be moved around.
+
+mod modules;
+
+pub use modules::{
+    ai_difficulty::{AiDifficulty, EngineSettings, HandicappedEngine},
+    bitboard::{BitBoard, Cells},
+    board::Board,
+    engine::{AiController, Engine},
+    engine_kind::EngineKind,
+    game::Game,
+    game_error::{GameError, GameResult},
+    game_mode::GameMode,
+    game_record::{self, GameRecord},
+    game_state::{BitPosition, GameState, Position},
+    game_status::GameStatus,
+    greedy::GreedyEngine,
+    input_error::InputError,
+    learning::{LearningEngine, ValueTable},
+    mcts::MctsEngine,
+    menace::{Matchbox, Matchboxes, MenaceEngine},
+    minimax::MinimaxEngine,
+    move_record::{parse_coords, MoveRecord},
+    player::Player,
+    player_controller::{PlayerAction, PlayerController, ScriptedController},
+    player_symbol::PlayerSymbol,
+    random::Rng,
+    replay::Replay,
+    save_file,
+    solver::{Outcome, Solution, SolutionTable, STANDARD_GAMES, STANDARD_POSITIONS},
+    symmetry::Symmetry,
+    tournament::{Crosstable, EngineFactory, PairResult, Rating, Tournament},
+    transposition_table::{Bound, TableEntry, TranspositionTable},
+    variant::Variant,
+};
@@ -1,19 +1,19 @@
 // Noughts and Crosses game with a simple AI opponent
 
-use anyhow::{anyhow, bail, Result as AnyResult};
+use anyhow::{bail, Context, Result as AnyResult};
 
-use modules::{game::Game, player_symbol::PlayerSymbol};
 use std::{
     io::{self, Write},
-    ops::Range,
+    path::PathBuf,
+    thread,
+    time::Duration,
 };
 
-mod modules;
-
-use crate::modules::{
-    game_mode,
-    input_error::{self, InputError},
-    player_symbol,
+use x_and_o::{
+    game_record, save_file, AiController, AiDifficulty, Board, EngineKind, EngineSettings, Game,
+    GameError, GameMode, GameRecord, GameResult, GameStatus, InputError, LearningEngine,
+    MoveRecord, PlayerAction, PlayerController, PlayerSymbol, Replay, Rng, SolutionTable,
+    Tournament, ValueTable, Variant, STANDARD_GAMES, STANDARD_POSITIONS,
 };
 
 fn main() -> AnyResult<()> {
@@ -22,207 +22,942 @@ fn main() -> AnyResult<()> {
 }
 fn run() -> AnyResult<()> {
     title();
-    help();
-    let mut game = Game::new();
-    let board_range = [(0..3), (0..3)];
-    // TODO: stop asking for 2nd player's move if the user enters
-    //       single player mode, and setup Greedy AI for
-    //       it.
-    // loop to get how many players and the symbol of player 1
-    let num_players = ask_for_num_players(&mut game, board_range.clone())?;
-    let player_1_symbol = ask_symbol_for_player_1(&mut game, board_range.clone())?;
-    if num_players == 1 {
-        game.set_game_mode(game_mode::GameMode::SinglePlayer);
-    } else {
-        game.set_game_mode(game_mode::GameMode::TwoPlayer);
+    let start_up = start_up_from_args()?;
+    let (mut game, mut difficulties, options) = match start_up {
+        StartUp::Usage => {
+            usage();
+            return Ok(());
+        }
+        StartUp::Replay(path) => return replay_games(&path),
+        StartUp::Tournament(options) => return run_tournament(&options),
+        StartUp::Solve(options) => return solve(&options),
+        StartUp::Train(options) => return train(&options),
+        StartUp::Load(path, options) => {
+            let game =
+                save_file::load_game(&path).with_context(|| format!("Could not load {}", path))?;
+            println!("Loaded {}\n", path);
+            let difficulties = assign_to_ais(*game.game_mode(), &options.difficulty);
+            (game, difficulties, options)
+        }
+        StartUp::NewGame(options) => {
+            let (game, difficulties) = new_game(&options)?;
+            (game, difficulties, options)
+        }
+    };
+    let engines = assign_to_ais(*game.game_mode(), &options.engine).map(Option::unwrap_or_default);
+    let settings = options.player_settings(*game.game_mode());
+    attach_controllers(&mut game, &mut difficulties, &engines, &settings)?;
+    if let Some(count) = options.games {
+        return play_batch(&mut game, &difficulties, &engines, count);
     }
-
-    game.players_mut()[0].set_symbol(player_1_symbol);
-    game.players_mut()[1].set_symbol(player_1_symbol.opposite());
-    play_game(&mut game, board_range)?;
+    if difficulties.iter().any(Option::is_none) {
+        help();
+    }
+    play_game(
+        &mut game,
+        &mut difficulties,
+        &engines,
+        &settings,
+        options.delay,
+    )?;
     Ok(())
 }
 
-fn ask_for_num_players(game: &mut Game, board_range: [Range<u8>; 2]) -> AnyResult<u8> {
-    let mut valid_input = false;
-    let prompt = "How many players? (1 or 2): ";
-    let input = String::new();
-    while !valid_input {
-        match get_string_input(prompt.to_string()) {
-            Ok(input) => {
-                if let Err(e) = parse_input(&input) {
-                    println!("Invalid input: {}", e);
-                    continue;
-                }
-                if input == "1" || input == "2" {
-                    break;
-                } else {
-                    valid_input = false;
-                    println!("Invalid input");
-                    continue;
+/// The AI difficulty of each player, or None for a person
+type Difficulties = [Option<AiDifficulty>; 2];
+
+/// The kind of engine each player uses if it is an AI
+type Engines = [EngineKind; 2];
+
+/// The engine settings for each player, which differ in the file
+/// a learning AI keeps what it knows in
+type Settings = [EngineSettings; 2];
+
+/// What the program was asked to do on the command line
+enum StartUp {
+    /// Set up a new game, asking for anything the options leave out
+    NewGame(GameOptions),
+    /// Carry on a saved game, from `--load <file>`,
+    /// optionally with new AI difficulties, engines and a delay
+    Load(String, GameOptions),
+    /// Step through recorded games, from `replay <file>`
+    Replay(String),
+    /// Play the AI difficulties and engines against each other, from `tournament`
+    Tournament(GameOptions),
+    /// Solve every position of the board, from `solve`
+    Solve(GameOptions),
+    /// Teach the learning engine by playing it against itself or another AI, from `train`
+    Train(GameOptions),
+    /// Show the command line options, from `--help`
+    Usage,
+}
+
+/// Game settings given on the command line.
+/// Anything left as None is asked for or takes its default.
+#[derive(Default)]
+struct GameOptions {
+    players: Option<u8>,
+    /// Player 1's symbol
+    symbol: Option<PlayerSymbol>,
+    /// The symbol that moves first
+    first: Option<PlayerSymbol>,
+    /// Levels for the AI players in order, the last one repeating if there are more AIs
+    difficulty: Vec<AiDifficulty>,
+    /// Engines for the AI players, given the same way as the levels
+    engine: Vec<EngineKind>,
+    variant: Option<Variant>,
+    /// Width and height
+    size: Option<(u8, u8)>,
+    win_length: Option<u8>,
+    /// Pause after each AI move
+    delay: Option<Duration>,
+    /// Longest an AI may think about a move
+    think: Option<Duration>,
+    /// Threads each AI searches with
+    threads: Option<usize>,
+    /// Number of AI-vs-AI games to play without showing them,
+    /// or in a tournament the number of games per pairing
+    games: Option<u32>,
+    /// Seed for a tournament's random choices
+    seed: Option<u64>,
+    /// File to write the solver's table to
+    export: Option<String>,
+    /// Files learning AIs load from and save to, given the same way as the levels
+    state: Vec<String>,
+}
+
+impl GameOptions {
+    /// Checks whether any option that only makes sense for a new game was given
+    fn sets_up_game(&self) -> bool {
+        self.players.is_some()
+            || self.symbol.is_some()
+            || self.first.is_some()
+            || self.variant.is_some()
+            || self.size.is_some()
+            || self.win_length.is_some()
+            || self.games.is_some()
+    }
+    /// Creates the board for the chosen variant, size and win length.
+    /// The win length defaults to the variant's, or to the shorter side
+    /// of the board when only a size is given.
+    fn board(&self) -> GameResult<Board> {
+        let variant = self.variant.unwrap_or(Variant::Standard);
+        let (mut width, mut height, mut win_length) = variant.size();
+        if let Some((new_width, new_height)) = self.size {
+            width = new_width;
+            height = new_height;
+            if self.variant.is_none() {
+                win_length = width.min(height);
+            }
+        }
+        Board::with_size(width, height, self.win_length.unwrap_or(win_length))
+    }
+    /// Gets the thinking time, threads and learning file for the AI engines,
+    /// using the first learning file given
+    fn engine_settings(&self) -> EngineSettings {
+        EngineSettings {
+            time_limit: self.think,
+            threads: self.threads.unwrap_or(1),
+            state_file: self.state.first().map(PathBuf::from),
+            save_state: true,
+        }
+    }
+    /// Gets the engine settings for each player in `game_mode`,
+    /// giving each AI the next learning file in turn
+    fn player_settings(&self, game_mode: GameMode) -> Settings {
+        assign_to_ais(game_mode, &self.state).map(|state| EngineSettings {
+            state_file: state.map(PathBuf::from),
+            ..self.engine_settings()
+        })
+    }
+}
+
+fn start_up_from_args() -> AnyResult<StartUp> {
+    let mut args = std::env::args().skip(1);
+    let mut options = GameOptions::default();
+    let mut load = None;
+    let mut tournament = false;
+    let mut solve = false;
+    let mut train = false;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .with_context(|| format!("{} needs a value", arg))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(StartUp::Usage),
+            "replay" => return Ok(StartUp::Replay(value()?)),
+            "tournament" => tournament = true,
+            "solve" => solve = true,
+            "train" => train = true,
+            "--export" => options.export = Some(value()?),
+            "--load" => load = Some(value()?),
+            "--state" => options.state = value()?.split(',').map(String::from).collect(),
+            "--players" => {
+                options.players = match value()?.as_str() {
+                    "0" => Some(0),
+                    "1" => Some(1),
+                    "2" => Some(2),
+                    other => bail!("--players must be 0, 1 or 2, not '{}'", other),
                 }
             }
-            Err(e) if e.to_string() == anyhow!("Invalid input").to_string() => {
-                println!("Invalid input");
-                continue;
+            "--symbol" => options.symbol = Some(value()?.parse()?),
+            "--first" => options.first = Some(value()?.parse()?),
+            "--difficulty" => {
+                options.difficulty = value()?
+                    .split(',')
+                    .map(|level| level.parse())
+                    .collect::<GameResult<Vec<AiDifficulty>>>()?;
+            }
+            "--engine" => {
+                options.engine = value()?
+                    .split(',')
+                    .map(|engine| engine.parse())
+                    .collect::<GameResult<Vec<EngineKind>>>()?;
+            }
+            "--variant" => options.variant = Some(value()?.parse()?),
+            "--size" => options.size = Some(parse_size(&value()?)?),
+            "--win-length" => {
+                let win_length = value()?;
+                options.win_length = Some(
+                    win_length
+                        .parse()
+                        .with_context(|| format!("Invalid win length '{}'", win_length))?,
+                );
+            }
+            "--delay" => {
+                let delay = value()?;
+                let millis = delay
+                    .parse()
+                    .with_context(|| format!("Invalid delay '{}'", delay))?;
+                options.delay = Some(Duration::from_millis(millis));
+            }
+            "--think" => {
+                let think = value()?;
+                let millis = think
+                    .parse()
+                    .with_context(|| format!("Invalid thinking time '{}'", think))?;
+                options.think = Some(Duration::from_millis(millis));
+            }
+            "--threads" => {
+                let threads = value()?;
+                options.threads = match threads.parse() {
+                    Ok(0) | Err(_) => {
+                        bail!("--threads must be a positive number, not '{}'", threads)
+                    }
+                    Ok(threads) => Some(threads),
+                };
             }
-            Err(e) => anyhow::bail!(e),
+            "--games" => {
+                let games = value()?;
+                options.games = match games.parse() {
+                    Ok(0) | Err(_) => bail!("--games must be a positive number, not '{}'", games),
+                    Ok(games) => Some(games),
+                };
+            }
+            "--seed" => {
+                let seed = value()?;
+                options.seed = Some(
+                    seed.parse()
+                        .with_context(|| format!("Invalid seed '{}'", seed))?,
+                );
+            }
+            other => bail!("Unknown argument: {}. Try --help", other),
         }
     }
-    // Once we get here, we know the input is valid
-    if input == "1" {
-        Ok(1)
-    } else if input == "2" {
-        Ok(2)
-    } else {
-        bail!(anyhow!("Invalid input"));
+    if tournament {
+        return Ok(StartUp::Tournament(options));
+    }
+    if solve {
+        return Ok(StartUp::Solve(options));
+    }
+    if train {
+        return Ok(StartUp::Train(options));
+    }
+    if options.games.is_some() {
+        match options.players {
+            None => options.players = Some(0),
+            Some(0) => {}
+            Some(_) => bail!("--games is only for games between two AIs, with --players 0"),
+        }
+    }
+    match load {
+        Some(_) if options.sets_up_game() => {
+            bail!("Only --difficulty, --engine, --think, --threads, --state and --delay can be used with --load, the rest comes from the file")
+        }
+        Some(path) => Ok(StartUp::Load(path, options)),
+        None => Ok(StartUp::NewGame(options)),
     }
 }
-fn ask_symbol_for_player_1(
+
+/// Parses a board size given as `WIDTHxHEIGHT`, or one number for a square board
+fn parse_size(text: &str) -> AnyResult<(u8, u8)> {
+    let (width, height) = text.split_once('x').unwrap_or((text, text));
+    match (width.parse::<u8>(), height.parse::<u8>()) {
+        (Ok(width), Ok(height)) => Ok((width, height)),
+        _ => bail!("Board size should look like 3x3 or 3, not '{}'", text),
+    }
+}
+
+/// Sets up a new game from the command line options, asking the player
+/// for anything they leave out. Also returns the difficulty of each AI player.
+fn new_game(options: &GameOptions) -> AnyResult<(Game, Difficulties)> {
+    let mut game = Game::with_board(options.board()?);
+    let num_players = match options.players {
+        Some(num_players) => num_players,
+        None => ask_for_num_players()?,
+    };
+    let game_mode = match num_players {
+        0 => GameMode::ZeroPlayer,
+        1 => GameMode::SinglePlayer,
+        _ => GameMode::TwoPlayer,
+    };
+    game.set_game_mode(game_mode);
+    let mut difficulties = assign_to_ais(game_mode, &options.difficulty);
+    for (index, difficulty) in difficulties.iter_mut().enumerate() {
+        if game_mode.is_ai(index) && difficulty.is_none() {
+            *difficulty = Some(ask_for_difficulty(index)?);
+        }
+    }
+    let player_1_symbol = match (options.symbol, game_mode) {
+        (Some(symbol), _) => symbol,
+        // Nobody is around to choose
+        (None, GameMode::ZeroPlayer) => PlayerSymbol::Cross,
+        (None, _) => ask_symbol_for_player_1()?,
+    };
+
+    game.players_mut()[0].set_symbol(player_1_symbol);
+    game.players_mut()[1].set_symbol(player_1_symbol.opposite());
+    if let Some(first) = options.first {
+        let index = if first == player_1_symbol { 0 } else { 1 };
+        game.set_first_player(index)?;
+    }
+    Ok((game, difficulties))
+}
+
+/// Gives each AI player in `game_mode` the next of `choices` in turn,
+/// such as levels or engines, repeating the last one if there are more AIs
+fn assign_to_ais<T: Clone>(game_mode: GameMode, choices: &[T]) -> [Option<T>; 2] {
+    let mut choices = choices.iter().cloned();
+    let mut choice = None;
+    let mut assigned = [None, None];
+    for (index, slot) in assigned.iter_mut().enumerate() {
+        if game_mode.is_ai(index) {
+            choice = choices.next().or(choice);
+            slot.clone_from(&choice);
+        }
+    }
+    assigned
+}
+
+/// Gives each player the AI for its engine and difficulty or, if the game mode
+/// has a person playing it, the terminal.
+/// Asks for the difficulty of any AI player that doesn't have one yet.
+/// Fails if both AIs would learn in the same file, as each would
+/// overwrite what the other saved.
+fn attach_controllers(
     game: &mut Game,
-    board_range: [Range<u8>; 2],
-) -> AnyResult<PlayerSymbol> {
-    let mut valid_input = false;
+    difficulties: &mut Difficulties,
+    engines: &Engines,
+    settings: &Settings,
+) -> AnyResult<()> {
+    let state_files = (0..2)
+        .filter(|&index| game.game_mode().is_ai(index))
+        .map(|index| engines[index].state_file(&settings[index]))
+        .collect::<Vec<_>>();
+    if let [Some(first), Some(second)] = &state_files[..] {
+        if first == second {
+            bail!(
+                "Both AIs would learn in {}, give each its own file with --state <file>,<file>",
+                first.display()
+            );
+        }
+    }
+    for (index, difficulty) in difficulties.iter_mut().enumerate() {
+        if !game.game_mode().is_ai(index) {
+            *difficulty = None;
+            game.set_controller(index, Box::new(HumanController::new()))?;
+            continue;
+        }
+        let difficulty = match difficulty {
+            Some(difficulty) => *difficulty,
+            None => *difficulty.insert(ask_for_difficulty(index)?),
+        };
+        let engine = engines[index]
+            .engine(difficulty, game.board(), Rng::from_time(), &settings[index])
+            .with_context(|| format!("Could not set up the {} engine", engines[index]))?;
+        let engine = Box::new(engine);
+        game.set_controller(index, Box::new(AiController::new(engine)))?;
+    }
+    Ok(())
+}
+
+fn ask_for_num_players() -> AnyResult<u8> {
+    let prompt = "How many players? (0, 1 or 2): ";
+    loop {
+        match get_string_input(prompt.to_string()) {
+            Ok(input) if input == "0" => return Ok(0),
+            Ok(input) if input == "1" => return Ok(1),
+            Ok(input) if input == "2" => return Ok(2),
+            Ok(_) => println!("Invalid input"),
+            Err(GameError::Input(e)) => println!("{}", e),
+            Err(e) => bail!(e),
+        }
+    }
+}
+fn ask_for_difficulty(player_index: usize) -> AnyResult<AiDifficulty> {
+    let prompt = format!(
+        "AI difficulty for player {}? (easy, medium, hard or impossible): ",
+        player_index + 1
+    );
+    loop {
+        match get_string_input(prompt.to_string()) {
+            Ok(input) => match input.parse::<AiDifficulty>() {
+                Ok(difficulty) => return Ok(difficulty),
+                Err(e) => println!("Invalid input: {}", e),
+            },
+            Err(GameError::Input(e)) => println!("{}", e),
+            Err(e) => bail!(e),
+        }
+    }
+}
+fn ask_symbol_for_player_1() -> AnyResult<PlayerSymbol> {
     let prompt = "Symbol for player 1? (X or O): ";
-    let input = String::new();
-    while !valid_input {
+    loop {
         match get_string_input(prompt.to_string()) {
-            Ok(mut input) => {
-                if let Err(e) = parse_input(&input) {
-                    println!("Invalid input: {}", e);
-                    continue;
-                } else {
-                    input = input.trim().to_string();
+            Ok(input) if input.eq_ignore_ascii_case("X") => return Ok(PlayerSymbol::Cross),
+            Ok(input) if input.eq_ignore_ascii_case("O") => return Ok(PlayerSymbol::Nought),
+            Ok(_) => println!("Invalid input"),
+            Err(GameError::Input(e)) => println!("{}", e),
+            Err(e) => bail!(e),
+        }
+    }
+}
+
+/// Lets the user pick a game from a record file and step through it
+fn replay_games(path: &str) -> AnyResult<()> {
+    let records = game_record::read_records(path)
+        .with_context(|| format!("Could not read games from {}", path))?;
+    let record = match records.len() {
+        0 => bail!("There are no games in {}", path),
+        1 => records[0].clone(),
+        count => {
+            for (index, record) in records.iter().enumerate() {
+                println!(
+                    "{}. {} (X) vs {} (O), {} {}",
+                    index + 1,
+                    record.tag("X").unwrap_or("?"),
+                    record.tag("O").unwrap_or("?"),
+                    record.tag("Date").unwrap_or("?"),
+                    record.result()
+                );
+            }
+            let prompt = format!("Which game? (1-{}): ", count);
+            loop {
+                match get_string_input(prompt.clone()) {
+                    Ok(input) => match input.parse::<usize>() {
+                        Ok(number) if (1..=count).contains(&number) => {
+                            break records[number - 1].clone();
+                        }
+                        _ => println!("Invalid input"),
+                    },
+                    Err(GameError::Input(e)) => println!("{}", e),
+                    Err(e) => bail!(e),
                 }
             }
-            Err(e) if e.to_string() == anyhow!("Invalid input").to_string() => {
-                println!("Invalid input");
+        }
+    };
+    let mut replay = Replay::new(record)?;
+    replay_help();
+    loop {
+        show_replay_position(&replay);
+        let input = match get_string_input("Replay: ".to_string()) {
+            Ok(input) => input,
+            Err(GameError::Input(e)) => {
+                println!("{}", e);
                 continue;
             }
-            Err(e) => anyhow::bail!(e),
+            Err(e) => return Err(e.into()),
+        };
+        let result = match input.to_lowercase().as_str() {
+            "n" => replay.forward().map(|_| ()),
+            "p" => replay.back().map(|_| ()),
+            "s" => replay.jump_to(0),
+            "e" => replay.jump_to(replay.move_count()),
+            "h" => {
+                replay_help();
+                Ok(())
+            }
+            "q" => return Ok(()),
+            other => match other.parse::<usize>() {
+                Ok(ply) => replay.jump_to(ply),
+                Err(_) => Err(InputError::InvalidInput.into()),
+            },
+        };
+        if let Err(e) = result {
+            println!("{}\n", e);
         }
-        valid_input = true;
     }
-    // Once we get here, we know the input is valid
-    let player_symbol = if input == "X" {
-        PlayerSymbol::Cross
-    } else {
-        PlayerSymbol::Nought
+}
+
+fn show_replay_position(replay: &Replay) {
+    let board = match replay.last_move() {
+        Some(last_move) => {
+            println!(
+                "Move {} of {}: {}",
+                replay.ply(),
+                replay.move_count(),
+                last_move
+            );
+            let (x, y) = last_move.coords();
+            replay.board().to_string_highlighting(x, y)
+        }
+        None => {
+            println!("Start of the game ({} moves)", replay.move_count());
+            replay.board().to_string()
+        }
     };
-    Ok(player_symbol)
+    println!("{}", board);
+    if replay.ply() == replay.move_count() {
+        println!("Result: {}\n", replay.record().result());
+    }
+}
+
+fn replay_help() {
+    println!("Enter n for the next move or p for the previous one.");
+    println!("Enter a move number to jump to it, or s or e for the start or end.");
+    println!("Enter h to see this again or q to quit.\n");
 }
 
 fn help() {
     println!("Enter coordinates in the format x,y.");
+    println!("Enter u to undo a move or r to redo it.");
+    println!("Enter save <file> or load <file> to save or load a game.");
+    println!("Enter record <file> to add the game to a game record file.");
     println!("Enter q to quit.\n");
 }
 
+/// Prints the command line options
+fn usage() {
+    println!("Usage: x-and-o [options]");
+    println!("       x-and-o --load <file> [--difficulty <level>] [--engine <name>]");
+    println!("                             [--think <ms>] [--threads <N>] [--delay <ms>]");
+    println!("       x-and-o replay <file>");
+    println!("       x-and-o solve [--export <file>] [--size <W>x<H>] [--win-length <K>]");
+    println!("       x-and-o tournament [--difficulty <levels>] [--engine <names>] [--games <N>]");
+    println!("                          [--think <ms>] [--threads <N>] [--seed <N>]");
+    println!("                          [--variant <name>] [--size <W>x<H>] [--win-length <K>]");
+    println!("       x-and-o train [--games <N>] [--engine <name>] [--difficulty <level>]");
+    println!(
+        "                     [--state <file>] [--seed <N>] [--size <W>x<H>] [--win-length <K>]\n"
+    );
+    println!("Options left out are asked for when the game starts.\n");
+    println!("  --players <0|1|2>        Watch two AIs, play the AI or play another person");
+    println!("  --symbol <X|O>           Player 1's symbol");
+    println!("  --first <X|O>            Which symbol moves first (default: player 1's)");
+    println!("  --difficulty <level>     easy, medium, hard or impossible, or one per AI");
+    println!("                           separated by commas, e.g. easy,hard");
+    println!("  --engine <name>          classic (greedy and minimax) or mcts (Monte Carlo");
+    println!("                           tree search), learner (learns from its games) or");
+    println!("                           menace (Michie's matchbox learner), or one per AI");
+    println!("                           like --difficulty");
+    println!("  --variant <name>         standard (3x3, 3 in a row) or gomoku (15x15, 5 in a row)");
+    println!("  --size <W>x<H>           Board size, or one number for a square board");
+    println!("  --win-length <K>         How many in a row wins");
+    println!("  --think <ms>             Longest an AI may think about a move; hard and");
+    println!("                           impossible then search as deep as time allows");
+    println!("  --threads <N>            Threads each AI searches with (default: 1)");
+    println!("  --delay <ms>             Pause after each AI move");
+    println!("  --games <N>              Play N games between two AIs and count the results,");
+    println!("                           in a tournament N games per pair of AIs, or when");
+    println!("                           training N games (default: 1000)");
+    println!("  --seed <N>               Seed for a tournament, to repeat its results");
+    println!("  --export <file>          Write the solved positions to a file");
+    println!("  --state <file>           File the learner or MENACE keeps what it knows in");
+    println!("                           (default: learner.txt or menace.txt), or one per AI");
+    println!("                           like --difficulty; tournaments only read it");
+    println!("  --load <file>            Carry on a saved game");
+    println!("  -h, --help               Show this help");
+}
+
 fn title() {
     let title = "Noughts and Crosses";
     println!("{:-^1$}\n", title, 80);
 }
 
-fn play_game(game: &mut Game, board_range: [Range<u8>; 2]) -> AnyResult<()> {
-    let current_player_symbol = game.current_player().symbol();
+fn play_game(
+    game: &mut Game,
+    difficulties: &mut Difficulties,
+    engines: &Engines,
+    settings: &Settings,
+    delay: Option<Duration>,
+) -> AnyResult<()> {
     while !game.game_over() {
-        let coords: (u8, u8) = (255, 255);
-        let valid_input = false;
-
-        do_current_players_turn(
-            game,
-            current_player_symbol,
-            valid_input,
-            coords,
-            &board_range,
-        )?;
-        if game.board_mut().is_game_over() {
-            break;
-        }
-        game.get_next_player();
-    }
-    if game.winner().is_some() {
-        println!("Player {} wins!", game.winner().unwrap());
-    } else {
-        println!("It's a draw!");
+        println!("{}", game.board());
+        let index = game.current_player_index();
+        let player = game.current_player();
+        match game.play_turn()? {
+            PlayerAction::Move(x, y) => {
+                println!("Player {} plays {},{}\n", player, x + 1, y + 1);
+                if let Some(delay) = delay.filter(|_| !game.is_human(index)) {
+                    thread::sleep(delay);
+                }
+            }
+            PlayerAction::Undo => take_back(game, Game::undo, "Took back"),
+            PlayerAction::Redo => take_back(game, Game::redo, "Replayed"),
+            PlayerAction::Save(path) => match save_file::save_game(game, &path) {
+                Ok(()) => println!("Saved the game to {}\n", path),
+                Err(e) => println!("Could not save to {}: {}\n", path, e),
+            },
+            PlayerAction::Load(path) => match save_file::load_game(&path) {
+                Ok(loaded) => {
+                    *game = loaded;
+                    attach_controllers(game, difficulties, engines, settings)?;
+                    println!("Loaded {}\n", path);
+                }
+                Err(e) => println!("Could not load {}: {}\n", path, e),
+            },
+            PlayerAction::Record(path) => match record_game(game, difficulties, engines, &path) {
+                Ok(()) => println!("Recorded the game in {}\n", path),
+                Err(e) => println!("Could not record the game in {}: {}\n", path, e),
+            },
+            PlayerAction::Quit => {
+                println!("Goodbye!");
+                return Ok(());
+            }
+        }
+    }
+    match game.status() {
+        GameStatus::Won { winner, line } => {
+            println!("{}", game.board().to_string_highlighting_cells(line));
+            println!("Player {} wins!", winner);
+        }
+        _ => {
+            println!("{}", game.board());
+            println!("It's a draw!");
+        }
     }
     Ok(())
 }
 
-fn do_current_players_turn(
+/// Plays `count` games between the AIs without showing the moves,
+/// then prints how many each player won, drew and lost
+fn play_batch(
     game: &mut Game,
-    current_player_symbol: player_symbol::PlayerSymbol,
-    valid_input: bool,
-    coords: (u8, u8),
-    board_range: &[Range<u8>; 2],
-) -> Result<(), anyhow::Error> {
-    println!("{}", game.board());
-    // ask for and make move
-    // TODO: rewrite this so that it's not so ugly, and so that
-    //       it works for both single and two player modes
-
-    // if game.game_mode() == &game_mode::GameMode::SinglePlayer
-    //     && game.players()[game.current_player_index()] == game.players()[1] {
-    // let prompt = format!("Player {}'s turn: ", current_player_symbol);
-    // make_move(valid_input, prompt, board_range, coords, game)?;
-    // Ok(())
-    //     } else {
-    //         let prompt = format!("Player {}'s turn: ", current_player_symbol);
-    //         make_move(valid_input, prompt, board_range, coords, game)?;
-    //         Ok(())
-    //     }
-}
-
-fn make_move(
-    mut valid_input: bool,
-    prompt: String,
-    board_range: &[Range<u8>; 2],
-    mut coords: (u8, u8),
-    game: &mut Game,
-) -> Result<(), anyhow::Error> {
-    while !valid_input {
-        if let Err(e) = get_string_input(prompt.clone()) {
-            if e.to_string() == anyhow!("Invalid input").to_string() {
-                println!("Invalid input");
-                valid_input = false;
-                continue;
-            } else {
-                return Err(e);
+    difficulties: &Difficulties,
+    engines: &Engines,
+    count: u32,
+) -> AnyResult<()> {
+    // Wins for each player, then draws
+    let mut wins = [0; 2];
+    let mut draws = 0;
+    for number in 1..=count {
+        game.restart();
+        while !game.game_over() {
+            game.play_turn()?;
+        }
+        println!("Game {}: {}", number, game.status());
+        match game.winner() {
+            Some(winner) => {
+                if let Some(index) = game.players().iter().position(|p| *p == winner) {
+                    wins[index] += 1;
+                }
             }
+            None => draws += 1,
+        }
+    }
+    println!("\nResults after {} games:", count);
+    for (index, player) in game.players().iter().enumerate() {
+        println!(
+            "{} as {}: {} wins, {} draws, {} losses",
+            player_name(game, difficulties, engines, index),
+            player.symbol(),
+            wins[index],
+            draws,
+            wins[1 - index]
+        );
+    }
+    Ok(())
+}
+
+/// Plays a round-robin tournament between every chosen engine at every
+/// chosen AI difficulty, or all difficulties of the classic engine
+/// if none were chosen, and prints the crosstable
+fn run_tournament(options: &GameOptions) -> AnyResult<()> {
+    let seed = options.seed.unwrap_or_else(|| Rng::from_time().next_u64());
+    let mut tournament = Tournament::new(seed).with_board(&options.board()?);
+    if let Some(games) = options.games {
+        tournament = tournament.with_games_per_pairing(games);
+    }
+    let levels = if options.difficulty.is_empty() {
+        vec![
+            AiDifficulty::Easy,
+            AiDifficulty::Medium,
+            AiDifficulty::Hard,
+            AiDifficulty::Impossible,
+        ]
+    } else {
+        options.difficulty.clone()
+    };
+    let engines = if options.engine.is_empty() {
+        vec![EngineKind::Classic]
+    } else {
+        options.engine.clone()
+    };
+    // Every game starts from what the learning engines knew before the
+    // tournament, so the results only depend on the seed
+    let settings = EngineSettings {
+        save_state: false,
+        ..options.engine_settings()
+    };
+    let board = options.board()?;
+    for kind in engines {
+        // Find out about unreadable files now rather than in the middle of a game
+        kind.engine(AiDifficulty::Easy, &board, Rng::new(seed), &settings)
+            .with_context(|| format!("Could not set up the {} engine", kind))?;
+        for &level in &levels {
+            let settings = settings.clone();
+            tournament.register(&ai_name(kind, level), move |board, rng| {
+                let engine = kind.engine(level, board, rng, &settings);
+                Box::new(engine.expect("the engine could be set up before the tournament"))
+            });
+        }
+    }
+    println!("Seed: {}\n", seed);
+    print!("{}", tournament.run()?);
+    Ok(())
+}
+
+/// Solves every position of the chosen board, checks the counts
+/// against the known ones for 3x3 and optionally exports the table
+fn solve(options: &GameOptions) -> AnyResult<()> {
+    let board = options.board()?;
+    if board.width() as usize * board.height() as usize > 12 {
+        bail!("Only boards with up to 12 cells can be solved");
+    }
+    let table = SolutionTable::solve(&board);
+    println!("Positions: {}", table.len());
+    println!("Games: {}", table.games());
+    if let Some(solution) = table.get(&board, PlayerSymbol::Cross) {
+        println!(
+            "With perfect play the first player gets a {} after {} moves",
+            solution.outcome, solution.distance
+        );
+    }
+    if (board.width(), board.height(), board.win_length()) == (3, 3, 3) {
+        if table.matches_standard_counts() {
+            println!("Self-check passed");
         } else {
-            let parse_result = parse_input(&prompt);
-            match parse_result {
-                Err(e) if e.to_string() == "Invalid input" => {
-                    bail!(InputError::InvalidInput);
-                }
-                Err(e) if e.to_string() == "Invalid coordinates" => {
-                    bail!(InputError::InvalidCoordinates);
-                }
-                Err(e) => bail!(e),
-                Ok((x_, y_)) => {
-                    let x_in_range = board_range[0].contains(&x_);
-                    let y_in_range = board_range[1].contains(&y_);
-                    if !(x_in_range && y_in_range) {
-                        bail!(InputError::InvalidCoordinates);
-                    }
-                    coords.0 = x_;
-                    coords.1 = y_;
-                }
+            bail!(
+                "Self-check failed: expected {} positions and {} games",
+                STANDARD_POSITIONS,
+                STANDARD_GAMES
+            );
+        }
+    }
+    if let Some(path) = &options.export {
+        table
+            .export(path)
+            .with_context(|| format!("Could not export to {}", path))?;
+        println!("Exported the table to {}", path);
+    }
+    Ok(())
+}
+
+/// Teaches the learning engine by playing it against itself, or against
+/// the chosen engine and difficulty, printing how each tenth of the games
+/// went so its progress shows, then saves what it learned
+fn train(options: &GameOptions) -> AnyResult<()> {
+    let board = options.board()?;
+    let settings = options.engine_settings();
+    let mut rng = Rng::new(options.seed.unwrap_or_else(|| Rng::from_time().next_u64()));
+    let path = EngineKind::Learner
+        .state_file(&settings)
+        .expect("the learner keeps a file");
+    let table = ValueTable::load_or_new(&path)
+        .with_context(|| format!("Could not load {}", path.display()))?;
+    if !table.is_empty() {
+        println!("Loaded {} positions from {}", table.len(), path.display());
+    }
+    let mut learner = LearningEngine::new(table, Rng::new(rng.next_u64()));
+    let mut opponent = match (options.engine.first(), options.difficulty.first()) {
+        (None, None) => None,
+        (Some(EngineKind::Learner), _) => {
+            bail!("Leave out --engine to train the learner against itself")
+        }
+        (kind, level) => {
+            let kind = kind.copied().unwrap_or_default();
+            let level = level.copied().unwrap_or(AiDifficulty::Hard);
+            // A learning opponent only gets a file of its own
+            let settings = EngineSettings {
+                state_file: options.state.get(1).map(PathBuf::from),
+                ..settings.clone()
+            };
+            let engine = kind.engine(level, &board, Rng::new(rng.next_u64()), &settings)?;
+            Some((ai_name(kind, level), engine))
+        }
+    };
+    let games = options.games.unwrap_or(1_000);
+    match &opponent {
+        Some((name, _)) => println!("Training against {} for {} games\n", name, games),
+        None => println!("Training against itself for {} games\n", games),
+    }
+    let batch = games.div_ceil(10);
+    let mut played = 0;
+    while played < games {
+        let count = batch.min(games - played);
+        let (results, side) = match &mut opponent {
+            Some((_, engine)) => (learner.train_against(&board, engine, count)?, ""),
+            None => (learner.train_self_play(&board, count)?, " as X"),
+        };
+        println!(
+            "Games {}-{}: {} wins, {} draws, {} losses{}",
+            played + 1,
+            played + count,
+            results.wins,
+            results.draws,
+            results.losses,
+            side
+        );
+        played += count;
+    }
+    learner
+        .table()
+        .save(&path)
+        .with_context(|| format!("Could not save {}", path.display()))?;
+    println!(
+        "\nSaved {} positions to {}",
+        learner.table().len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Names an AI after its engine and difficulty,
+/// leaving the engine out for the classic one
+fn ai_name(kind: EngineKind, difficulty: AiDifficulty) -> String {
+    match kind {
+        EngineKind::Classic => difficulty.to_string(),
+        kind => format!("{} {}", kind, difficulty),
+    }
+}
+
+/// Names the player at `player_index` after who controls them
+fn player_name(
+    game: &Game,
+    difficulties: &Difficulties,
+    engines: &Engines,
+    player_index: usize,
+) -> String {
+    match difficulties[player_index] {
+        Some(difficulty) => format!("AI ({})", ai_name(engines[player_index], difficulty)),
+        None => format!(
+            "Player {}",
+            game.players()[player_index].get_player_number()
+        ),
+    }
+}
+
+/// Appends `game` to a game record file, naming the players after who controls them
+fn record_game(
+    game: &Game,
+    difficulties: &Difficulties,
+    engines: &Engines,
+    path: &str,
+) -> GameResult<()> {
+    let mut record = GameRecord::from_game(game);
+    record.set_tag("Event", "Terminal game");
+    for (index, player) in game.players().iter().enumerate() {
+        let name = player_name(game, difficulties, engines, index);
+        record.set_tag(&player.symbol().to_string(), &name);
+    }
+    game_record::append_record(path, &record)
+}
+
+/// Undoes or redoes a move with `step`, then keeps going
+/// until it is a human's turn again so the AI doesn't just replay its move
+fn take_back(game: &mut Game, step: fn(&mut Game) -> GameResult<MoveRecord>, verb: &str) {
+    match step(game) {
+        Ok(record) => println!("{} move {}\n", verb, record),
+        Err(e) => {
+            println!("{}\n", e);
+            return;
+        }
+    }
+    while !game.is_human(game.current_player_index()) {
+        match step(game) {
+            Ok(record) => println!("{} move {}\n", verb, record),
+            Err(_) => break,
+        }
+    }
+}
+
+/// Asks a human at the terminal for moves
+struct HumanController {}
+
+impl HumanController {
+    fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PlayerController for HumanController {
+    fn choose_action(&mut self, game: &Game) -> GameResult<PlayerAction> {
+        let prompt = format!("Player {}'s turn: ", game.current_player_symbol());
+        make_move(prompt, game)
+    }
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+fn make_move(prompt: String, game: &Game) -> GameResult<PlayerAction> {
+    let board = game.board();
+    loop {
+        let input = match get_string_input(prompt.clone()) {
+            Ok(input) => input,
+            Err(GameError::Input(e)) => {
+                println!("{}", e);
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        if input.eq_ignore_ascii_case("q") {
+            return Ok(PlayerAction::Quit);
+        }
+        if input.eq_ignore_ascii_case("u") {
+            return Ok(PlayerAction::Undo);
+        }
+        if input.eq_ignore_ascii_case("r") {
+            return Ok(PlayerAction::Redo);
+        }
+        if let Some((command, path)) = input.split_once(' ') {
+            if command.eq_ignore_ascii_case("save") {
+                return Ok(PlayerAction::Save(path.trim().to_string()));
+            }
+            if command.eq_ignore_ascii_case("load") {
+                return Ok(PlayerAction::Load(path.trim().to_string()));
             }
-            if game.board().get(coords.0, coords.1).is_some() {
-                println!("Position already taken");
-                valid_input = false;
+            if command.eq_ignore_ascii_case("record") {
+                return Ok(PlayerAction::Record(path.trim().to_string()));
+            }
+        }
+        let (x, y) = match parse_input(&input, board.width(), board.height()) {
+            Err(e) => {
+                println!("{}", e);
                 continue;
-            } else {
-                valid_input = true;
-                let current_player = game.current_player();
-                game.board_mut().set(coords.0, coords.1, current_player)?;
             }
+            Ok(coords) => coords,
+        };
+        if board.get(x, y).is_some() {
+            println!("{}", GameError::CellOccupied { x, y });
+            continue;
         }
+        return Ok(PlayerAction::Move(x, y));
     }
-    Ok(())
 }
 
-fn get_string_input(prompt: String) -> AnyResult<String> {
+fn get_string_input(prompt: String) -> GameResult<String> {
     let mut input = String::new();
     if prompt.ends_with(' ') || prompt.ends_with(':') || prompt.ends_with('?') {
         print!("{}", prompt);
@@ -230,37 +965,43 @@ fn get_string_input(prompt: String) -> AnyResult<String> {
     } else {
         println!("{}", prompt);
     }
-    if let Err(e) = io::stdin().read_line(&mut input) {
-        return Err(anyhow!(e));
+    if io::stdin().read_line(&mut input)? == 0 {
+        let no_input = io::Error::new(io::ErrorKind::UnexpectedEof, "No more input");
+        return Err(GameError::Io(no_input));
     }
-    let input_chars = input.trim().replace(' ', "").chars().collect::<Vec<char>>();
     let input = input.trim();
-    if input.is_empty() || !(3..=4).contains(&input.len()) {
-        bail!(input_error::InputError::InvalidInput);
-    }
-    if !(input_chars[0].is_numeric() && input_chars[1] == ',' && input_chars[2].is_numeric()) {
-        bail!(input_error::InputError::InvalidCoordinates);
+    if input.is_empty() {
+        return Err(InputError::InvalidInput.into());
     }
 
     Ok(input.to_string())
 }
 
-fn parse_input(input: &str) -> AnyResult<(u8, u8)> {
+/// Parses 1-based "x,y" coordinates for a `width` by `height` board
+/// into 0-based ones
+fn parse_input(input: &str, width: u8, height: u8) -> GameResult<(u8, u8)> {
     let input = input.trim().replace(' ', "");
-    let mut parts = input.split(',').collect::<Vec<&str>>();
+    let mut parts = input.split(',');
     let x = parts
-        .pop()
-        .ok_or(anyhow::anyhow!("Missing x coordinate"))?
+        .next()
+        .ok_or(InputError::InvalidCoordinates)?
         .to_string();
     let y = parts
-        .pop()
-        .ok_or(anyhow::anyhow!("Missing y coordinate"))?
+        .next()
+        .ok_or(InputError::InvalidCoordinates)?
         .to_string();
-    let x = x.parse::<u8>()?;
-    let y = y.parse::<u8>()?;
-    if (1..=3).contains(&x) && (1..=3).contains(&y) {
+    if parts.next().is_some() {
+        return Err(InputError::InvalidCoordinates.into());
+    }
+    let x = x
+        .parse::<u8>()
+        .map_err(|_| InputError::InvalidCoordinates)?;
+    let y = y
+        .parse::<u8>()
+        .map_err(|_| InputError::InvalidCoordinates)?;
+    if (1..=width).contains(&x) && (1..=height).contains(&y) {
         Ok((x - 1, y - 1))
     } else {
-        Err(anyhow::anyhow!("Invalid input"))
+        Err(InputError::InvalidCoordinates.into())
     }
 }
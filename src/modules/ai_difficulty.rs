@@ -0,0 +1,245 @@
+use crate::modules::{
+    board::Board,
+    engine::Engine,
+    game::Game,
+    game_error::{GameError, GameResult},
+    greedy::GreedyEngine,
+    minimax::MinimaxEngine,
+    random::Rng,
+};
+
+use std::{fmt::Display, path::PathBuf, str::FromStr, time::Duration};
+
+/// Most cells a board can have for the full minimax to search
+/// to the end of the game in reasonable time
+const FULL_SEARCH_CELLS: usize = 9;
+
+/// How far ahead Hard and Impossible look on bigger boards
+const BIG_BOARD_DEPTH: i32 = 4;
+
+/// How much time and how many threads an AI may use, on top of its difficulty,
+/// and where engines that learn keep what they know
+#[derive(Clone, PartialEq, Debug)]
+pub struct EngineSettings {
+    /// Longest to think about each move, or None to search as usual
+    pub time_limit: Option<Duration>,
+    /// How many threads to search with
+    pub threads: usize,
+    /// File a learning engine loads from and saves to,
+    /// or None for its usual file
+    pub state_file: Option<PathBuf>,
+    /// Whether a learning engine saves to its file after each game,
+    /// or only reads it, e.g. so a tournament leaves it as it was
+    pub save_state: bool,
+}
+
+impl Default for EngineSettings {
+    fn default() -> Self {
+        Self {
+            time_limit: None,
+            threads: 1,
+            state_file: None,
+            save_state: true,
+        }
+    }
+}
+
+/// How strong the single player opponent is
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum AiDifficulty {
+    Easy,
+    Medium,
+    Hard,
+    Impossible,
+}
+
+impl AiDifficulty {
+    /// Gets the chance of the opponent playing a random move
+    /// instead of the move its engine picked
+    pub fn blunder_rate(&self) -> f64 {
+        match self {
+            AiDifficulty::Easy => 0.5,
+            AiDifficulty::Medium => 0.2,
+            AiDifficulty::Hard => 0.05,
+            AiDifficulty::Impossible => 0.0,
+        }
+    }
+    /// Creates the engine for this difficulty on `board`.
+    /// Easy uses the greedy engine, Medium a minimax that only looks
+    /// two moves ahead, and Hard and Impossible the full minimax,
+    /// cut down to a few moves ahead on boards bigger than 3x3.
+    pub fn engine(&self, board: &Board, rng: Rng) -> HandicappedEngine {
+        self.engine_with(board, rng, &EngineSettings::default())
+    }
+    /// Creates the engine for this difficulty on `board` using `settings`.
+    /// With a time limit Hard and Impossible search as deep as the time
+    /// allows on any board, while Easy and Medium are unchanged apart from the limit.
+    pub fn engine_with(
+        &self,
+        board: &Board,
+        rng: Rng,
+        settings: &EngineSettings,
+    ) -> HandicappedEngine {
+        let cells = board.width() as usize * board.height() as usize;
+        let minimax = |max_depth: Option<i32>| {
+            let engine = match max_depth {
+                Some(max_depth) => MinimaxEngine::with_max_depth(max_depth),
+                None => MinimaxEngine::new(),
+            }
+            .with_threads(settings.threads);
+            match settings.time_limit {
+                Some(time_limit) => engine.with_time_limit(time_limit),
+                None => engine,
+            }
+        };
+        let engine: Box<dyn Engine> = match self {
+            AiDifficulty::Easy => Box::new(GreedyEngine::new()),
+            AiDifficulty::Medium => Box::new(minimax(Some(2))),
+            AiDifficulty::Hard | AiDifficulty::Impossible
+                if settings.time_limit.is_none() && cells > FULL_SEARCH_CELLS =>
+            {
+                Box::new(minimax(Some(BIG_BOARD_DEPTH)))
+            }
+            AiDifficulty::Hard | AiDifficulty::Impossible => Box::new(minimax(None)),
+        };
+        HandicappedEngine::new(engine, self.blunder_rate(), rng)
+    }
+}
+
+impl Display for AiDifficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            AiDifficulty::Easy => "easy",
+            AiDifficulty::Medium => "medium",
+            AiDifficulty::Hard => "hard",
+            AiDifficulty::Impossible => "impossible",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AiDifficulty {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "1" | "e" | "easy" => Ok(AiDifficulty::Easy),
+            "2" | "m" | "medium" => Ok(AiDifficulty::Medium),
+            "3" | "h" | "hard" => Ok(AiDifficulty::Hard),
+            "4" | "i" | "impossible" => Ok(AiDifficulty::Impossible),
+            _ => Err(GameError::Parse(format!("Unknown difficulty: {}", s))),
+        }
+    }
+}
+
+/// Wraps an engine so it sometimes plays a random legal move instead
+pub struct HandicappedEngine {
+    engine: Box<dyn Engine>,
+    blunder_rate: f64,
+    rng: Rng,
+}
+
+impl HandicappedEngine {
+    pub fn new(engine: Box<dyn Engine>, blunder_rate: f64, rng: Rng) -> Self {
+        Self {
+            engine,
+            blunder_rate,
+            rng,
+        }
+    }
+    /// Overrides the chance of playing a random move, clamped to `0.0..=1.0`
+    pub fn with_blunder_rate(mut self, blunder_rate: f64) -> Self {
+        self.blunder_rate = blunder_rate.clamp(0.0, 1.0);
+        self
+    }
+    pub fn blunder_rate(&self) -> f64 {
+        self.blunder_rate
+    }
+}
+
+impl Engine for HandicappedEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        if self.blunder_rate > 0.0 && self.rng.chance(self.blunder_rate) {
+            return self.rng.choose(&game.board().empty_cells()).copied();
+        }
+        self.engine.choose_move(game)
+    }
+    fn game_finished(&mut self, game: &Game) -> GameResult<()> {
+        self.engine.game_finished(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    /// Stands in for an engine that must never be asked for a move
+    struct NeverAsked;
+
+    impl Engine for NeverAsked {
+        fn choose_move(&mut self, _game: &Game) -> Option<(u8, u8)> {
+            panic!("every move should have been a blunder");
+        }
+    }
+
+    /// Plays a whole game with `engine` moving for both sides
+    fn self_play(engine: &mut HandicappedEngine) -> Vec<(u8, u8)> {
+        let mut game = Game::new();
+        while !game.game_over() {
+            let (x, y) = engine.choose_move(&game).unwrap();
+            game.apply_move(x, y).unwrap();
+        }
+        game.history()
+            .iter()
+            .map(|record| record.coords())
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_moves() {
+        for difficulty in [AiDifficulty::Easy, AiDifficulty::Medium] {
+            let games = |seed| {
+                let mut engine = difficulty.engine(&Board::new(), Rng::new(seed));
+                (0..10).map(|_| self_play(&mut engine)).collect::<Vec<_>>()
+            };
+            assert_eq!(games(42), games(42), "{}", difficulty);
+        }
+    }
+
+    #[test]
+    fn never_blunders_at_a_rate_of_0() {
+        let game = Game::from_notation("XX./OO./... x").unwrap();
+        let mut engine = AiDifficulty::Easy
+            .engine(&Board::new(), Rng::new(1))
+            .with_blunder_rate(0.0);
+        for _ in 0..100 {
+            assert_eq!(engine.choose_move(&game), Some((2, 0)));
+        }
+    }
+
+    #[test]
+    fn always_blunders_at_a_rate_of_1() {
+        let game = Game::from_notation("XX./OO./... x").unwrap();
+        let mut engine =
+            HandicappedEngine::new(Box::new(NeverAsked), 0.5, Rng::new(1)).with_blunder_rate(1.0);
+        let mut played = HashSet::new();
+        for _ in 0..100 {
+            let (x, y) = engine.choose_move(&game).unwrap();
+            assert_eq!(game.board().get(x, y), None);
+            played.insert((x, y));
+        }
+        // Any empty cell, not just the best one
+        assert_eq!(played.len(), game.board().empty_cells().len());
+    }
+
+    #[test]
+    fn clamps_the_blunder_rate() {
+        let engine = |rate| {
+            HandicappedEngine::new(Box::new(NeverAsked), 0.5, Rng::new(1)).with_blunder_rate(rate)
+        };
+        assert_eq!(engine(1.5).blunder_rate(), 1.0);
+        assert_eq!(engine(-0.5).blunder_rate(), 0.0);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::modules::{
+    board::{zobrist_key, Board, LINE_DIRECTIONS, NOUGHT_TO_MOVE_KEY},
+    game_error::{GameError, GameResult},
+    player_symbol::PlayerSymbol,
+};
+
+use std::sync::Arc;
+
+/// Compact board for engines: one bit per cell for each side, so moves
+/// can be made, taken back and checked for a win without allocating.
+/// Cell (x, y) is bit `y * width + x`, which limits boards to 128 cells.
+///
+/// Minimax and Monte Carlo tree search play on one of these
+/// whenever the board fits, and on the `Board` itself when it doesn't.
+#[derive(Clone, Debug)]
+pub struct BitBoard {
+    width: u8,
+    height: u8,
+    win_length: u8,
+    /// Cells held by X, then by O
+    sides: [u128; 2],
+    /// Zobrist hash of the cells, the same as `Board::hash` gives
+    hash: u64,
+    /// Shared between copies, as they never change
+    lines: Arc<WinMasks>,
+}
+
+/// Every winning line on a board, worked out once per board size
+#[derive(Debug)]
+struct WinMasks {
+    all: Vec<u128>,
+    /// The lines through each cell
+    through: Vec<Vec<u128>>,
+}
+
+impl BitBoard {
+    /// Most cells a bitboard can hold
+    pub const MAX_CELLS: usize = 128;
+
+    /// Creates an empty `width` by `height` board won with `win_length` in a row
+    pub fn new(width: u8, height: u8, win_length: u8) -> GameResult<Self> {
+        // Checks the sizes the same way a normal board does
+        Board::with_size(width, height, win_length)?;
+        let cells = width as usize * height as usize;
+        if cells > Self::MAX_CELLS {
+            return Err(GameError::InvalidSetup(format!(
+                "Bitboards only fit boards of up to {} cells, not {}x{}",
+                Self::MAX_CELLS,
+                width,
+                height
+            )));
+        }
+        let mut lines = WinMasks {
+            all: Vec::new(),
+            through: vec![Vec::new(); cells],
+        };
+        for y in 0..height as i16 {
+            for x in 0..width as i16 {
+                for (dx, dy) in LINE_DIRECTIONS {
+                    let end_x = x + dx * (win_length as i16 - 1);
+                    let end_y = y + dy * (win_length as i16 - 1);
+                    if !(0..width as i16).contains(&end_x) || !(0..height as i16).contains(&end_y) {
+                        continue;
+                    }
+                    let mut mask = 0;
+                    for step in 0..win_length as i16 {
+                        let cell = (y + dy * step) * width as i16 + x + dx * step;
+                        mask |= 1u128 << cell;
+                    }
+                    lines.all.push(mask);
+                    for cell in Cells(mask) {
+                        lines.through[cell as usize].push(mask);
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            win_length,
+            sides: [0; 2],
+            hash: 0,
+            lines: Arc::new(lines),
+        })
+    }
+    /// Copies the position on `board`
+    pub fn from_board(board: &Board) -> GameResult<Self> {
+        let mut bitboard = Self::new(board.width(), board.height(), board.win_length())?;
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                if let Some(player) = board.get(x, y) {
+                    bitboard.make_move(bitboard.cell(x, y), player.symbol());
+                }
+            }
+        }
+        Ok(bitboard)
+    }
+    /// Gets the number of columns
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+    /// Gets the number of rows
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+    /// Gets how many in a row are needed to win
+    pub fn win_length(&self) -> u8 {
+        self.win_length
+    }
+    /// Gets the bit number of (x, y), which must be on the board
+    pub fn cell(&self, x: u8, y: u8) -> u8 {
+        y * self.width + x
+    }
+    /// Gets the (x, y) coordinates of a bit number
+    pub fn coords(&self, cell: u8) -> (u8, u8) {
+        (cell % self.width, cell / self.width)
+    }
+    /// Gets who holds `cell`, if anyone
+    pub fn get(&self, cell: u8) -> Option<PlayerSymbol> {
+        let bit = 1u128 << cell;
+        if self.sides[0] & bit != 0 {
+            Some(PlayerSymbol::Cross)
+        } else if self.sides[1] & bit != 0 {
+            Some(PlayerSymbol::Nought)
+        } else {
+            None
+        }
+    }
+    /// Gets the cells held by `symbol` as a bitmask
+    pub fn side(&self, symbol: PlayerSymbol) -> u128 {
+        self.sides[side_index(symbol)]
+    }
+    /// Gets the empty cells as a bitmask
+    pub fn empty(&self) -> u128 {
+        let cells = self.width as u32 * self.height as u32;
+        let all = if cells == 128 {
+            u128::MAX
+        } else {
+            (1u128 << cells) - 1
+        };
+        all & !(self.sides[0] | self.sides[1])
+    }
+    /// Goes through the empty cells, lowest bit first
+    pub fn moves(&self) -> Cells {
+        Cells(self.empty())
+    }
+    /// Checks whether every cell is taken
+    pub fn is_full(&self) -> bool {
+        self.empty() == 0
+    }
+    /// Places `symbol` on `cell`, which must be empty
+    pub fn make_move(&mut self, cell: u8, symbol: PlayerSymbol) {
+        debug_assert!(self.get(cell).is_none(), "cell {} is already taken", cell);
+        self.sides[side_index(symbol)] |= 1u128 << cell;
+        self.hash ^= zobrist_key(cell as usize, symbol);
+    }
+    /// Takes `symbol` back off `cell`
+    pub fn unmake_move(&mut self, cell: u8, symbol: PlayerSymbol) {
+        debug_assert!(
+            self.get(cell) == Some(symbol),
+            "{:?} isn't on cell {}",
+            symbol,
+            cell
+        );
+        self.sides[side_index(symbol)] &= !(1u128 << cell);
+        self.hash ^= zobrist_key(cell as usize, symbol);
+    }
+    /// Gets the Zobrist hash of the cells, the same as `Board::hash`
+    /// for the same position
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+    /// Gets the hash of the position with `to_move` to play,
+    /// the same as `Board::position_hash` for the same position
+    pub fn position_hash(&self, to_move: PlayerSymbol) -> u64 {
+        match to_move {
+            PlayerSymbol::Cross => self.hash,
+            PlayerSymbol::Nought => self.hash ^ NOUGHT_TO_MOVE_KEY,
+        }
+    }
+    /// Checks whether `symbol` has `win_length` in a row anywhere
+    pub fn has_won(&self, symbol: PlayerSymbol) -> bool {
+        let side = self.side(symbol);
+        self.lines.all.iter().any(|&mask| mask & !side == 0)
+    }
+    /// Checks whether `symbol` has a line through `cell`.
+    /// After a move this is all that needs checking.
+    pub fn wins_through(&self, cell: u8, symbol: PlayerSymbol) -> bool {
+        let side = self.side(symbol);
+        self.lines.through[cell as usize]
+            .iter()
+            .any(|&mask| mask & !side == 0)
+    }
+}
+
+/// Position of a symbol's bitmask in `BitBoard::sides`
+fn side_index(symbol: PlayerSymbol) -> usize {
+    match symbol {
+        PlayerSymbol::Cross => 0,
+        PlayerSymbol::Nought => 1,
+    }
+}
+
+/// Iterates over the set bits of a mask, lowest first
+#[derive(Copy, Clone, Debug)]
+pub struct Cells(pub u128);
+
+impl Iterator for Cells {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.0 == 0 {
+            return None;
+        }
+        let cell = self.0.trailing_zeros() as u8;
+        // Clear the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(cell)
+    }
+}
@@ -1,90 +1,238 @@
-use crate::modules::player::Player;
-use anyhow::Result as AnyResult;
+use crate::modules::{
+    game_error::{GameError, GameResult},
+    game_status::GameStatus,
+    player::Player,
+    player_symbol::PlayerSymbol,
+    random::Rng,
+    symmetry::Symmetry,
+};
 
-use std::fmt::Display;
+use std::fmt::{Display, Write};
 
-#[derive(Clone, Copy)]
-pub(crate) struct Board {
-    board: [[Option<Player>; 3]; 3],
+/// Directions a winning line can run in, as (dx, dy) steps:
+/// right, down, down-right and up-right
+pub(crate) const LINE_DIRECTIONS: [(i16, i16); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// Mixed into `Board::position_hash` and `BitBoard::position_hash` when O is to move
+pub(crate) const NOUGHT_TO_MOVE_KEY: u64 = 0x5A4F_4252_4953_5421;
+
+/// An m,n,k board: `width` by `height` cells,
+/// won by the first player to get `win_length` in a row
+#[derive(Clone, PartialEq, Debug)]
+pub struct Board {
+    /// Cells stored row by row, so (x, y) lives at `y * width + x`
+    board: Vec<Option<Player>>,
     width: u8,
     height: u8,
-    winner: Option<Player>,
+    win_length: u8,
+    /// Number of cells that aren't empty, to spot a full board
+    occupied: usize,
+    /// Kept up to date as cells are set and cleared
+    status: GameStatus,
+    /// Zobrist hash of the cells, also kept up to date
+    hash: u64,
 }
 
 impl Default for Board {
     fn default() -> Self {
         Self {
-            board: [[None; 3]; 3],
+            board: vec![None; 9],
             width: 3,
             height: 3,
-            winner: None,
+            win_length: 3,
+            occupied: 0,
+            status: GameStatus::InProgress,
+            hash: 0,
         }
     }
 }
 
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        self.render(f, &[])
+    }
+}
+
+impl Board {
+    pub fn new() -> Self {
+        let board: Board = Board::default();
+        board
+    }
+    /// Creates an empty `width` by `height` board
+    /// that is won by getting `win_length` in a row
+    pub fn with_size(width: u8, height: u8, win_length: u8) -> GameResult<Self> {
+        if width == 0 || height == 0 {
+            return Err(GameError::InvalidSetup(
+                "The board must be at least 1x1".to_string(),
+            ));
+        }
+        if win_length == 0 || win_length > width.max(height) {
+            return Err(GameError::InvalidSetup(format!(
+                "Win length must be between 1 and {} on a {}x{} board",
+                width.max(height),
+                width,
+                height
+            )));
+        }
+        Ok(Self {
+            board: vec![None; width as usize * height as usize],
+            width,
+            height,
+            win_length,
+            occupied: 0,
+            status: GameStatus::InProgress,
+            hash: 0,
+        })
+    }
+    /// Creates an empty board the same size as this one, with the same win length
+    pub fn empty_like(&self) -> Self {
+        Self::with_size(self.width, self.height, self.win_length)
+            .expect("an existing board's size is valid")
+    }
+    /// Draws the board like `Display` does, but with brackets
+    /// around the cell at (x, y), e.g. to show the last move
+    pub fn to_string_highlighting(&self, x: u8, y: u8) -> String {
+        self.to_string_highlighting_cells(&[(x, y)])
+    }
+    /// Draws the board with brackets around every cell in `cells`,
+    /// e.g. to show the winning line
+    pub fn to_string_highlighting_cells(&self, cells: &[(u8, u8)]) -> String {
+        let mut text = String::new();
+        // Writing to a String can't fail
+        let _ = self.render(&mut text, cells);
+        text
+    }
+    /// Draws the board with column and row labels,
+    /// putting brackets around the `highlight` cells
+    fn render(&self, f: &mut impl Write, highlight: &[(u8, u8)]) -> std::fmt::Result {
+        // Row labels are right-aligned so the grid lines up on big boards
+        let label_width = self.height.to_string().len();
         // Add labels to the top of the board
-        write!(f, "  ")?;
+        write!(f, "{:1$}", "", label_width + 1)?;
         for x in 0..self.width {
-            write!(f, "{}", x + 1)?;
             if x < self.width - 1 {
-                write!(f, "   ")?;
+                write!(f, "{:<4}", x + 1)?;
+            } else {
+                write!(f, "{}", x + 1)?;
             }
         }
         writeln!(f)?;
+        let separator = vec!["---"; self.width as usize].join("+");
         for y in 0..self.height {
-            for x in 0..self.width {
-                let mut symbol = match self.board[y as usize][x as usize] {
-                    Some(player) => format!("{}", player),
-                    None => format!("{}", '.'), // (y * self.width) + x),
-                };
-                if x == 0 {
-                    symbol = format!("{} {}", y + 1, symbol);
-                }
-                write!(f, "{}", symbol)?;
-                if x < self.width - 1 {
-                    write!(f, " | ")?;
-                }
-            }
-            writeln!(f)?;
+            let cells = (0..self.width)
+                .map(|x| {
+                    let symbol = match self.get(x, y) {
+                        Some(player) => format!("{}", player),
+                        None => format!("{}", '.'),
+                    };
+                    if highlight.contains(&(x, y)) {
+                        format!("[{}]", symbol)
+                    } else {
+                        format!(" {} ", symbol)
+                    }
+                })
+                .collect::<Vec<String>>();
+            let row = format!("{:>2$}{}", y + 1, cells.join("|"), label_width);
+            writeln!(f, "{}", row.trim_end())?;
             if y < self.height - 1 {
-                writeln!(f, " ---+---+---")?;
+                writeln!(f, "{:2$}{}", "", separator, label_width)?;
             }
         }
         Ok(())
     }
-}
-
-impl Board {
-    pub(crate) fn new() -> Self {
-        let board: Board = Board::default();
-        board
+    /// Gets the number of columns
+    pub fn width(&self) -> u8 {
+        self.width
     }
-    pub(crate) fn set(&mut self, x: u8, y: u8, player: Player) -> AnyResult<()> {
-        let (x, y) = if x >= self.width || y >= self.height {
-            return Err(anyhow::anyhow!("Invalid position"));
+    /// Gets the number of rows
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+    /// Gets how many in a row are needed to win
+    pub fn win_length(&self) -> u8 {
+        self.win_length
+    }
+    /// Gets the position of (x, y) in the cell vector, or None if it is off the board
+    fn index(&self, x: u8, y: u8) -> Option<usize> {
+        if x >= self.width || y >= self.height {
+            None
         } else {
-            (x as usize, y as usize)
+            Some(y as usize * self.width as usize + x as usize)
+        }
+    }
+    pub fn set(&mut self, x: u8, y: u8, player: Player) -> GameResult<()> {
+        let index = match self.index(x, y) {
+            Some(index) => index,
+            None => return Err(GameError::OutOfRange { x, y }),
         };
-        if self.board[y][x].is_some() {
-            return Err(anyhow::anyhow!("Position already taken"));
+        if self.board[index].is_some() {
+            return Err(GameError::CellOccupied { x, y });
+        }
+        self.board[index] = Some(player);
+        self.occupied += 1;
+        self.hash ^= zobrist_key(index, player.symbol());
+        // Only lines through the new cell can have been completed by it
+        if !self.status.is_over() {
+            if let Some(line) = self.win_through(x, y) {
+                self.status = GameStatus::Won {
+                    winner: player,
+                    line,
+                };
+            } else if self.occupied == self.board.len() {
+                self.status = GameStatus::Draw;
+            }
         }
-        self.board[y][x] = Some(player);
         Ok(())
     }
-    pub(crate) fn get(&self, x: u8, y: u8) -> Option<Player> {
-        let (x, y) = if x >= self.width || y >= self.height {
-            return None;
-        } else {
-            (x as usize, y as usize)
+    /// Empties the cell at (x, y), e.g. to take a move back
+    pub fn clear(&mut self, x: u8, y: u8) -> GameResult<()> {
+        let index = match self.index(x, y) {
+            Some(index) => index,
+            None => return Err(GameError::OutOfRange { x, y }),
         };
-        self.board[y][x]
+        if let Some(player) = self.board[index].take() {
+            self.occupied -= 1;
+            self.hash ^= zobrist_key(index, player.symbol());
+            // Emptying a cell can't finish a game, but any win
+            // may have involved this cell, so look at the whole board again
+            if self.status.is_over() {
+                self.status = self.find_status();
+            }
+        }
+        Ok(())
+    }
+    /// Gets the Zobrist hash of the cells: two boards with the same symbols
+    /// in the same places have the same hash, and different ones almost never do
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+    /// Gets the hash of the position with `to_move` to play,
+    /// e.g. to look it up in a `TranspositionTable`
+    pub fn position_hash(&self, to_move: PlayerSymbol) -> u64 {
+        match to_move {
+            PlayerSymbol::Cross => self.hash,
+            PlayerSymbol::Nought => self.hash ^ NOUGHT_TO_MOVE_KEY,
+        }
+    }
+    pub fn get(&self, x: u8, y: u8) -> Option<Player> {
+        self.index(x, y).and_then(|index| self.board[index])
+    }
+    /// Gets the coordinates of every empty cell, row by row
+    pub fn empty_cells(&self) -> Vec<(u8, u8)> {
+        let mut cells = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y).is_none() {
+                    cells.push((x, y));
+                }
+            }
+        }
+        cells
     }
 
-    /// Check if a vector of 3 cells contain the same player, and are not empty
+    /// Check if a vector of cells contain the same player, and are not empty
     /// If both conditions are met, this is a win for that player, so return true
-    pub(crate) fn is_win_vec(self, cells: Vec<(u8, u8)>) -> bool {
+    pub fn is_win_vec(&self, cells: Vec<(u8, u8)>) -> bool {
         let mut player = None;
         // Loop through each cell in the vector
         for (x, y) in cells {
@@ -107,66 +255,577 @@ impl Board {
         }
         true
     }
+    /// Gets whether the game on this board is still going, won or drawn
+    pub fn status(&self) -> &GameStatus {
+        &self.status
+    }
     /// Check if the game is over
-    /// Returns true if the game is over
-    /// Uses is_win to check 3 cells at a time to determine if there is a winner
-    /// If there is no winner, checks if the board is full
-    /// If the board is full, the game is over
-    pub(crate) fn is_game_over(&mut self) -> bool {
-        // Check if there is a win, if there is, return true
-        self.find_win()
-    }
-    fn set_winner(&mut self, player: Option<Player>) {
-        self.winner = player;
-    }
-    fn find_win(&mut self) -> bool {
-        // Check if there is a win
-        // Check rows
+    /// Returns true if someone has won or the board is full
+    pub fn is_game_over(&self) -> bool {
+        self.status.is_over()
+    }
+    /// Works out the status from scratch by trying every line
+    /// of `win_length` cells in every direction
+    fn find_status(&self) -> GameStatus {
         for y in 0..self.height {
-            let cells = vec![(0, y), (1, y), (2, y)];
-            if self.is_win_vec(cells.clone()) {
-                if let Some(player) = self.get(cells[0].0, cells[0].1) {
-                    self.set_winner(Some(player));
+            for x in 0..self.width {
+                for direction in LINE_DIRECTIONS {
+                    let cells = match self.line_from(x, y, direction) {
+                        Some(cells) => cells,
+                        None => continue,
+                    };
+                    if self.is_win_vec(cells.clone()) {
+                        if let Some(winner) = self.get(x, y) {
+                            return GameStatus::Won {
+                                winner,
+                                line: cells,
+                            };
+                        }
+                    }
                 }
-                return true;
             }
         }
-        // Check columns
-        for x in 0..self.width {
-            let cells = vec![(x, 0), (x, 1), (x, 2)];
-            if self.is_win_vec(cells.clone()) {
-                self.winner = self.get(cells[0].0, cells[0].1);
-                return true;
-            }
+        if self.occupied == self.board.len() {
+            GameStatus::Draw
+        } else {
+            GameStatus::InProgress
         }
-        // Check diagonals
-        let cells = vec![(0, 0), (1, 1), (2, 2)];
-        if self.is_win_vec(cells.clone()) {
-            self.winner = self.get(cells[0].0, cells[0].1);
-            return true;
+    }
+    /// Finds a line of `win_length` cells through (x, y)
+    /// all held by whoever is at (x, y)
+    fn win_through(&self, x: u8, y: u8) -> Option<Vec<(u8, u8)>> {
+        for (dx, dy) in LINE_DIRECTIONS {
+            // Try each line that (x, y) is the `offset`th cell of
+            for offset in 0..self.win_length as i16 {
+                let start_x = u8::try_from(x as i16 - dx * offset);
+                let start_y = u8::try_from(y as i16 - dy * offset);
+                let (Ok(start_x), Ok(start_y)) = (start_x, start_y) else {
+                    continue;
+                };
+                if let Some(cells) = self.line_from(start_x, start_y, (dx, dy)) {
+                    if self.is_win_vec(cells.clone()) {
+                        return Some(cells);
+                    }
+                }
+            }
         }
-        let cells = vec![(2, 0), (1, 1), (0, 2)];
-        if self.is_win_vec(cells.clone()) {
-            self.winner = self.get(cells[0].0, cells[0].1);
-            return true;
+        None
+    }
+
+    /// Gets the `win_length` cells starting at (x, y) and stepping in `direction`,
+    /// or None if the line would run off the board
+    fn line_from(&self, x: u8, y: u8, direction: (i16, i16)) -> Option<Vec<(u8, u8)>> {
+        let (dx, dy) = direction;
+        let mut cells = Vec::with_capacity(self.win_length as usize);
+        for step in 0..self.win_length as i16 {
+            let cell_x = x as i16 + dx * step;
+            let cell_y = y as i16 + dy * step;
+            if !(0..self.width as i16).contains(&cell_x)
+                || !(0..self.height as i16).contains(&cell_y)
+            {
+                return None;
+            }
+            cells.push((cell_x as u8, cell_y as u8));
         }
-        // Check if the board is full
+        Some(cells)
+    }
+
+    /// Gets the winner, if there is one
+    pub fn get_winner(&self) -> Option<Player> {
+        self.status.winner()
+    }
+
+    /// Checks whether `symbol` has `win_length` in a row anywhere on the board
+    pub fn has_won(&self, symbol: PlayerSymbol) -> bool {
         for y in 0..self.height {
             for x in 0..self.width {
-                if self.get(x, y).is_none() {
-                    return false;
+                for direction in LINE_DIRECTIONS {
+                    let cells = match self.line_from(x, y, direction) {
+                        Some(cells) => cells,
+                        None => continue,
+                    };
+                    let owned =
+                        |&(x, y): &(u8, u8)| self.get(x, y).is_some_and(|p| p.symbol() == symbol);
+                    if cells.iter().all(owned) {
+                        return true;
+                    }
                 }
             }
         }
-        true
+        false
     }
 
-    pub fn get_winner(&mut self) -> Option<Player> {
-        // Get the winner, if there is one
-        if self.find_win() {
-            self.winner
+    /// Gets the board turned or flipped by `symmetry`.
+    /// Turning a board that isn't square swaps its width and height.
+    pub fn transformed(&self, symmetry: Symmetry) -> Board {
+        let (width, height) = if symmetry.swaps_sides() {
+            (self.height, self.width)
         } else {
-            None
+            (self.width, self.height)
+        };
+        let mut board = Board::with_size(width, height, self.win_length)
+            .expect("a turned board has a valid size");
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(player) = self.get(x, y) {
+                    let (new_x, new_y) = symmetry.apply(x, y, self.width, self.height);
+                    board
+                        .set(new_x, new_y, player)
+                        .expect("symmetries map each cell to a different one");
+                }
+            }
+        }
+        board
+    }
+    /// Gets the symmetries that keep the board the same shape:
+    /// all eight for a square board, or the four that don't swap the sides
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::ALL
+            .into_iter()
+            .filter(|symmetry| self.width == self.height || !symmetry.swaps_sides())
+            .collect()
+    }
+    /// Gets every rotation and reflection of the position
+    /// that keeps the board the same shape, with the symmetry giving each
+    pub fn symmetric_boards(&self) -> Vec<(Symmetry, Board)> {
+        self.symmetries()
+            .into_iter()
+            .map(|symmetry| (symmetry, self.transformed(symmetry)))
+            .collect()
+    }
+    /// Gets the representative of the position's symmetric boards, the same
+    /// for all of them, along with the symmetry that turns this board into it
+    pub fn canonical(&self) -> (Board, Symmetry) {
+        let key = |board: &Board| board.to_notation(PlayerSymbol::Cross);
+        self.symmetric_boards()
+            .into_iter()
+            .min_by_key(|(_, board)| key(board))
+            .map(|(symmetry, board)| (board, symmetry))
+            .expect("the identity is always a symmetry")
+    }
+    /// Finds a symmetry that turns this board into `other`, if there is one.
+    /// Moves on this board map to `other` with `Symmetry::apply`.
+    /// Boards of different sizes or win lengths never match.
+    pub fn symmetry_to(&self, other: &Board) -> Option<Symmetry> {
+        let shape = |board: &Board| (board.width, board.height, board.win_length);
+        if shape(self) != shape(other) {
+            return None;
+        }
+        self.symmetries()
+            .into_iter()
+            .find(|&symmetry| self.transformed(symmetry).board == other.board)
+    }
+    /// Maps the move (x, y) on this board to the same move on `other`,
+    /// if `other` is a rotation or reflection of this board
+    pub fn map_move_to(&self, other: &Board, x: u8, y: u8) -> Option<(u8, u8)> {
+        self.symmetry_to(other)
+            .map(|symmetry| symmetry.apply(x, y, self.width, self.height))
+    }
+
+    /// Writes the position as one line: each row from top to bottom
+    /// separated by `/`, with `X`, `O` or `.` for each cell,
+    /// then the side to move in lower case, e.g. `XO./.X./..O o`.
+    /// The win length follows if it isn't the shorter side of the board.
+    pub fn to_notation(&self, to_move: PlayerSymbol) -> String {
+        let rows = (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| match self.get(x, y) {
+                        Some(player) => player.to_string(),
+                        None => ".".to_string(),
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>();
+        let mut notation = format!("{} {}", rows.join("/"), to_move.to_string().to_lowercase());
+        if self.win_length != self.width.min(self.height) {
+            notation.push_str(&format!(" {}", self.win_length));
+        }
+        notation
+    }
+
+    /// Reads a position written by `to_notation`, returning the board
+    /// and the side to move. X is placed as player 1 and O as player 2,
+    /// matching a new `Game`. Positions that can't come up in a real game,
+    /// like too many of one symbol or both sides having won, are rejected.
+    pub fn from_notation(notation: &str) -> GameResult<(Board, PlayerSymbol)> {
+        let parse_error = |message: &str| GameError::Parse(message.to_string());
+        let fields = notation.split_whitespace().collect::<Vec<&str>>();
+        let (rows, to_move, win_length) = match fields[..] {
+            [rows, to_move] => (rows, to_move, None),
+            [rows, to_move, win_length] => (rows, to_move, Some(win_length)),
+            _ => {
+                return Err(parse_error(
+                    "Expected rows, the side to move and optionally a win length",
+                ))
+            }
+        };
+        let rows = rows.split('/').collect::<Vec<&str>>();
+        let width = rows[0].chars().count();
+        if rows.iter().any(|row| row.chars().count() != width) {
+            return Err(parse_error("Every row must be the same length"));
+        }
+        if width > u8::MAX as usize || rows.len() > u8::MAX as usize {
+            return Err(GameError::InvalidSetup(format!(
+                "The board can be at most {0}x{0}",
+                u8::MAX
+            )));
+        }
+        let (width, height) = (width as u8, rows.len() as u8);
+        let win_length = match win_length {
+            Some(win_length) => win_length
+                .parse::<u8>()
+                .map_err(|_| parse_error("The win length must be a number from 1 to 255"))?,
+            None => width.min(height),
+        };
+        let to_move = match to_move {
+            "x" => PlayerSymbol::Cross,
+            "o" => PlayerSymbol::Nought,
+            _ => {
+                return Err(GameError::Parse(format!(
+                    "The side to move must be x or o, not '{}'",
+                    to_move
+                )))
+            }
+        };
+
+        let mut board = Board::with_size(width, height, win_length)?;
+        let cross = Player::new(PlayerSymbol::Cross, 1);
+        let nought = Player::new(PlayerSymbol::Nought, 2);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                let player = match cell {
+                    'X' => cross,
+                    'O' => nought,
+                    '.' => continue,
+                    _ => {
+                        return Err(GameError::Parse(format!(
+                            "Unknown cell '{}', expected X, O or .",
+                            cell
+                        )))
+                    }
+                };
+                board.set(x as u8, y as u8, player)?;
+            }
+        }
+
+        let count = |player: Player| {
+            board
+                .board
+                .iter()
+                .filter(|&&cell| cell == Some(player))
+                .count()
+        };
+        let (crosses, noughts) = (count(cross), count(nought));
+        // Whoever went first has made either the same number of moves as
+        // the other side, if it's their turn, or one more if it isn't
+        let balanced = match to_move {
+            PlayerSymbol::Cross => crosses == noughts || noughts == crosses + 1,
+            PlayerSymbol::Nought => noughts == crosses || crosses == noughts + 1,
+        };
+        if !balanced {
+            return Err(GameError::ImpossiblePosition(format!(
+                "{} X and {} O with {} to move can't happen in a game",
+                crosses, noughts, to_move
+            )));
+        }
+        let cross_won = board.has_won(PlayerSymbol::Cross);
+        let nought_won = board.has_won(PlayerSymbol::Nought);
+        if cross_won && nought_won {
+            return Err(GameError::ImpossiblePosition(
+                "Both sides have won".to_string(),
+            ));
+        }
+        if (cross_won && to_move == PlayerSymbol::Cross)
+            || (nought_won && to_move == PlayerSymbol::Nought)
+        {
+            return Err(GameError::ImpossiblePosition(format!(
+                "{} has already won so it can't be their move",
+                to_move
+            )));
+        }
+        Ok((board, to_move))
+    }
+}
+
+/// Gets the Zobrist key for `symbol` on the cell at `index`.
+/// Keys come from the cell and symbol alone, so no table of them is needed.
+pub(crate) fn zobrist_key(index: usize, symbol: PlayerSymbol) -> u64 {
+    let side = match symbol {
+        PlayerSymbol::Cross => 0,
+        PlayerSymbol::Nought => 1,
+    };
+    Rng::new((index as u64) << 1 | side).next_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::bitboard::BitBoard;
+
+    #[test]
+    fn notation_round_trips_exactly() {
+        for notation in [
+            ".../.../... x",
+            "XO./.X./..O o",
+            "X../.O./... x",
+            "XOX/OXO/OXO x",
+            "X..../.O.../...../...../..... x 4",
+            "XO../.... x",
+        ] {
+            let (board, to_move) = Board::from_notation(notation).unwrap();
+            assert_eq!(board.to_notation(to_move), notation);
+        }
+    }
+
+    #[test]
+    fn reads_the_board_and_side_to_move() {
+        let (board, to_move) = Board::from_notation("XO./.X./..O o").unwrap();
+        assert_eq!(to_move, PlayerSymbol::Nought);
+        assert_eq!(
+            (board.width(), board.height(), board.win_length()),
+            (3, 3, 3)
+        );
+        assert_eq!(
+            board.get(0, 0).map(|p| p.symbol()),
+            Some(PlayerSymbol::Cross)
+        );
+        assert_eq!(
+            board.get(1, 0).map(|p| p.symbol()),
+            Some(PlayerSymbol::Nought)
+        );
+        assert_eq!(board.get(2, 0), None);
+    }
+
+    #[test]
+    fn rejects_too_many_of_one_symbol() {
+        for notation in [
+            "XX./.../... o",
+            "XX./.../... x",
+            "O../.../... o",
+            "XXX/O../... o",
+        ] {
+            assert!(
+                matches!(
+                    Board::from_notation(notation),
+                    Err(GameError::ImpossiblePosition(_))
+                ),
+                "{}",
+                notation
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_both_sides_having_won() {
+        let result = Board::from_notation("XXX/OOO/... x");
+        assert!(matches!(result, Err(GameError::ImpossiblePosition(_))));
+    }
+
+    #[test]
+    fn rejects_the_winner_being_to_move() {
+        assert!(Board::from_notation("XXX/OO./... x").is_err());
+        assert!(Board::from_notation("XXX/OO./... o").is_ok());
+    }
+
+    /// Gets the cells of `board` as notation, without the side to move
+    fn cells(board: &Board) -> String {
+        board.to_notation(PlayerSymbol::Cross).replace(" x", "")
+    }
+
+    #[test]
+    fn turns_and_flips_a_square_board() {
+        let (board, _) = Board::from_notation("XO./.../... o").unwrap();
+        let expected = [
+            (Symmetry::Identity, "XO./.../..."),
+            (Symmetry::Rotate90, "..X/..O/..."),
+            (Symmetry::Rotate180, ".../.../.OX"),
+            (Symmetry::Rotate270, ".../O../X.."),
+            (Symmetry::FlipHorizontal, ".OX/.../..."),
+            (Symmetry::FlipVertical, ".../.../XO."),
+            (Symmetry::FlipDiagonal, "X../O../..."),
+            (Symmetry::FlipAntiDiagonal, ".../..O/..X"),
+        ];
+        assert_eq!(board.symmetries(), Symmetry::ALL);
+        for (symmetry, notation) in expected {
+            assert_eq!(
+                cells(&board.transformed(symmetry)),
+                notation,
+                "{:?}",
+                symmetry
+            );
+        }
+    }
+
+    #[test]
+    fn turns_and_flips_a_board_that_is_not_square() {
+        let (board, _) = Board::from_notation("XO./... o").unwrap();
+        let expected = [
+            (Symmetry::Identity, "XO./..."),
+            (Symmetry::Rotate180, ".../.OX"),
+            (Symmetry::FlipHorizontal, ".OX/..."),
+            (Symmetry::FlipVertical, ".../XO."),
+        ];
+        assert_eq!(board.symmetries(), expected.map(|(symmetry, _)| symmetry));
+        for (symmetry, notation) in expected {
+            assert_eq!(
+                cells(&board.transformed(symmetry)),
+                notation,
+                "{:?}",
+                symmetry
+            );
+        }
+        // Turning it a quarter swaps the sides
+        assert_eq!(cells(&board.transformed(Symmetry::Rotate90)), ".X/.O/..");
+    }
+
+    #[test]
+    fn every_symmetric_board_has_the_same_canonical_board() {
+        for notation in [
+            "XO./.../... o",
+            "X../.O./..X o",
+            "XO../.... o",
+            "X.../.O../..X./.... o",
+        ] {
+            let (board, _) = Board::from_notation(notation).unwrap();
+            let (canonical, _) = board.canonical();
+            for (_, other) in board.symmetric_boards() {
+                let (other_canonical, symmetry) = other.canonical();
+                assert_eq!(other_canonical, canonical, "{}", notation);
+                assert_eq!(other.transformed(symmetry), canonical, "{}", notation);
+            }
+        }
+    }
+
+    #[test]
+    fn maps_moves_between_symmetric_boards_and_back() {
+        for notation in ["XO./.../... o", "XO../.... o"] {
+            let (board, _) = Board::from_notation(notation).unwrap();
+            for (_, other) in board.symmetric_boards() {
+                for y in 0..board.height() {
+                    for x in 0..board.width() {
+                        let (other_x, other_y) = board.map_move_to(&other, x, y).unwrap();
+                        assert!(other_x < other.width() && other_y < other.height());
+                        assert_eq!(other.map_move_to(&board, other_x, other_y), Some((x, y)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn boards_of_different_shapes_are_not_symmetric() {
+        let wide = Board::with_size(3, 2, 2).unwrap();
+        let tall = Board::with_size(2, 3, 2).unwrap();
+        assert_eq!(wide.symmetry_to(&tall), None);
+        assert_eq!(wide.map_move_to(&tall, 1, 2), None);
+        let longer_win = Board::with_size(3, 3, 2).unwrap();
+        assert_eq!(Board::new().symmetry_to(&longer_win), None);
+        assert_eq!(
+            Board::new().symmetry_to(&Board::new()),
+            Some(Symmetry::Identity)
+        );
+    }
+
+    #[test]
+    fn hash_kept_up_to_date_matches_one_worked_out_from_scratch() {
+        let players = [
+            Player::new(PlayerSymbol::Cross, 1),
+            Player::new(PlayerSymbol::Nought, 2),
+        ];
+        let mut board = Board::with_size(4, 4, 3).unwrap();
+        let mut rng = Rng::new(7);
+        for _ in 0..500 {
+            let (x, y) = (rng.below(4) as u8, rng.below(4) as u8);
+            if board.get(x, y).is_some() {
+                board.clear(x, y).unwrap();
+            } else {
+                board.set(x, y, players[rng.below(2)]).unwrap();
+            }
+            let mut from_scratch = 0;
+            for (index, cell) in board.board.iter().enumerate() {
+                if let Some(player) = cell {
+                    from_scratch ^= zobrist_key(index, player.symbol());
+                }
+            }
+            assert_eq!(board.hash(), from_scratch);
+            assert_eq!(BitBoard::from_board(&board).unwrap().hash(), from_scratch);
+        }
+        assert_ne!(
+            board.position_hash(PlayerSymbol::Cross),
+            board.position_hash(PlayerSymbol::Nought)
+        );
+    }
+
+    /// Places X on each of `cells` in turn on a new board,
+    /// then gets the winning line, if X has one
+    fn line_after(
+        width: u8,
+        height: u8,
+        win_length: u8,
+        cells: &[(u8, u8)],
+    ) -> Option<Vec<(u8, u8)>> {
+        let mut board = Board::with_size(width, height, win_length).unwrap();
+        let cross = Player::new(PlayerSymbol::Cross, 1);
+        for &(x, y) in cells {
+            board.set(x, y, cross).unwrap();
+        }
+        board.status().winning_line().map(|line| line.to_vec())
+    }
+
+    #[test]
+    fn finds_wins_on_a_board_that_is_not_square() {
+        // Down the last column of a wide board
+        let column = [(4, 0), (4, 1), (4, 2)];
+        assert_eq!(line_after(5, 3, 3, &column), Some(column.to_vec()));
+        // Along the bottom row of a tall board
+        let row = [(0, 4), (1, 4), (2, 4)];
+        assert_eq!(line_after(3, 5, 3, &row), Some(row.to_vec()));
+        // A line that would run off the short side isn't a win
+        assert_eq!(line_after(5, 2, 3, &[(0, 0), (0, 1)]), None);
+    }
+
+    #[test]
+    fn needs_the_whole_win_length_when_it_is_shorter_than_the_side() {
+        assert_eq!(line_after(5, 5, 4, &[(1, 1), (2, 1), (3, 1)]), None);
+        assert_eq!(
+            line_after(5, 5, 4, &[(1, 1), (2, 1), (3, 1), (4, 1)]),
+            Some(vec![(1, 1), (2, 1), (3, 1), (4, 1)])
+        );
+        // Filling a gap in the middle finds the line from its end
+        assert_eq!(
+            line_after(5, 5, 4, &[(0, 3), (1, 3), (3, 3), (2, 3)]),
+            Some(vec![(0, 3), (1, 3), (2, 3), (3, 3)])
+        );
+    }
+
+    #[test]
+    fn finds_wins_going_up_to_the_right() {
+        let diagonal = vec![(0, 3), (1, 2), (2, 1)];
+        // Whichever cell of the line is played last
+        for last in 0..diagonal.len() {
+            let mut cells = diagonal.clone();
+            let cell = cells.remove(last);
+            cells.push(cell);
+            assert_eq!(line_after(4, 4, 3, &cells), Some(diagonal.clone()));
+        }
+        // Taking back a cell off the line works the status out
+        // again from scratch, and still finds the line
+        let (mut board, _) = Board::from_notation("O.O./..X./.X../X... o 3").unwrap();
+        board.clear(0, 0).unwrap();
+        assert_eq!(board.status().winning_line(), Some(&diagonal[..]));
+    }
+
+    #[test]
+    fn rejects_malformed_notation() {
+        for notation in [
+            "",
+            ".../.../...",
+            ".../../... x",
+            ".../.../... z",
+            ".A./.../... x",
+        ] {
+            assert!(Board::from_notation(notation).is_err(), "{}", notation);
         }
     }
 }
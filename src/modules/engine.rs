@@ -0,0 +1,40 @@
+use crate::modules::{
+    game::Game,
+    game_error::{GameError, GameResult},
+    player_controller::{PlayerAction, PlayerController},
+};
+
+/// A move-picking algorithm that only needs to look at the game
+pub trait Engine {
+    /// Chooses a move for the current player of `game`,
+    /// or returns None if there is nowhere left to play
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)>;
+    /// Called when a game the engine is playing in has finished,
+    /// so engines that learn can learn from it
+    fn game_finished(&mut self, _game: &Game) -> GameResult<()> {
+        Ok(())
+    }
+}
+
+/// Lets an engine play as one of the players in a game
+pub struct AiController {
+    engine: Box<dyn Engine>,
+}
+
+impl AiController {
+    pub fn new(engine: Box<dyn Engine>) -> Self {
+        Self { engine }
+    }
+}
+
+impl PlayerController for AiController {
+    fn choose_action(&mut self, game: &Game) -> GameResult<PlayerAction> {
+        match self.engine.choose_move(game) {
+            Some((x, y)) => Ok(PlayerAction::Move(x, y)),
+            None => Err(GameError::NoMovesLeft),
+        }
+    }
+    fn game_finished(&mut self, game: &Game) -> GameResult<()> {
+        self.engine.game_finished(game)
+    }
+}
@@ -0,0 +1,126 @@
+use crate::modules::{
+    ai_difficulty::{AiDifficulty, EngineSettings, HandicappedEngine},
+    board::Board,
+    engine::Engine,
+    game_error::{GameError, GameResult},
+    learning::{LearningEngine, ValueTable},
+    mcts::MctsEngine,
+    menace::{Matchboxes, MenaceEngine},
+    random::Rng,
+};
+
+use std::{fmt::Display, path::PathBuf, str::FromStr};
+
+/// Which kind of engine plays for an AI player.
+/// The difficulty then sets how strong it is.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub enum EngineKind {
+    /// The greedy engine and minimax, as picked by `AiDifficulty::engine`
+    #[default]
+    Classic,
+    /// Monte Carlo tree search, with more playouts at higher difficulties
+    Mcts,
+    /// Learns from every game it plays, keeping what it knows in a file
+    Learner,
+    /// Michie's matchboxes and beads, also learning from every game
+    /// and keeping its boxes in a file
+    Menace,
+}
+
+impl EngineKind {
+    /// Gets the file engines of this kind keep what they have learned in:
+    /// the one in `settings` or else their usual one, or None if they don't learn
+    pub fn state_file(&self, settings: &EngineSettings) -> Option<PathBuf> {
+        let default_file = match self {
+            EngineKind::Classic | EngineKind::Mcts => return None,
+            EngineKind::Learner => "learner.txt",
+            EngineKind::Menace => "menace.txt",
+        };
+        Some(
+            settings
+                .state_file
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(default_file)),
+        )
+    }
+    /// Creates an engine of this kind for `difficulty` on `board`,
+    /// with the time and threads `settings` allow.
+    /// Fails if a learning engine's file can't be read.
+    pub fn engine(
+        &self,
+        difficulty: AiDifficulty,
+        board: &Board,
+        mut rng: Rng,
+        settings: &EngineSettings,
+    ) -> GameResult<HandicappedEngine> {
+        let engine: Box<dyn Engine> = match self {
+            EngineKind::Classic => return Ok(difficulty.engine_with(board, rng, settings)),
+            EngineKind::Mcts => {
+                let iterations = match difficulty {
+                    AiDifficulty::Easy => 200,
+                    AiDifficulty::Medium => 1_000,
+                    AiDifficulty::Hard => 5_000,
+                    AiDifficulty::Impossible => 20_000,
+                };
+                let mut engine = MctsEngine::new(Rng::new(rng.next_u64()))
+                    .with_iterations(iterations)
+                    .with_threads(settings.threads);
+                if let Some(time_limit) = settings.time_limit {
+                    engine = engine.with_time_limit(time_limit);
+                }
+                Box::new(engine)
+            }
+            EngineKind::Learner => {
+                let path = self.state_file(settings).expect("learners keep a file");
+                let table = ValueTable::load_or_new(&path)?;
+                let engine = LearningEngine::new(table, Rng::new(rng.next_u64()));
+                if settings.save_state {
+                    Box::new(engine.with_save_file(path))
+                } else {
+                    Box::new(engine)
+                }
+            }
+            EngineKind::Menace => {
+                let path = self.state_file(settings).expect("MENACE keeps a file");
+                let boxes = Matchboxes::load_or_new(&path)?;
+                let engine = MenaceEngine::new(boxes, Rng::new(rng.next_u64()));
+                if settings.save_state {
+                    Box::new(engine.with_save_file(path))
+                } else {
+                    Box::new(engine)
+                }
+            }
+        };
+        Ok(HandicappedEngine::new(
+            engine,
+            difficulty.blunder_rate(),
+            rng,
+        ))
+    }
+}
+
+impl Display for EngineKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            EngineKind::Classic => "classic",
+            EngineKind::Mcts => "mcts",
+            EngineKind::Learner => "learner",
+            EngineKind::Menace => "menace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for EngineKind {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "classic" => Ok(EngineKind::Classic),
+            "mcts" => Ok(EngineKind::Mcts),
+            "learner" => Ok(EngineKind::Learner),
+            "menace" => Ok(EngineKind::Menace),
+            _ => Err(GameError::Parse(format!("Unknown engine: {}", s))),
+        }
+    }
+}
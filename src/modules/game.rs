@@ -1,14 +1,23 @@
 use crate::modules::{
-    board::Board, game_mode::GameMode, player::Player, player_symbol::PlayerSymbol,
+    board::Board,
+    game_mode::GameMode,
+    game_status::GameStatus,
+    move_record::MoveRecord,
+    game_error::{GameError, GameResult},
+    player::Player,
+    player_controller::{PlayerAction, PlayerController},
+    player_symbol::PlayerSymbol,
 };
-use anyhow::{Result as AnyResult, bail, Error};
 
-pub(crate) struct Game {
+pub struct Game {
     board: Board,
     game_mode: GameMode,
     players: Vec<Player>,
+    controllers: Vec<Option<Box<dyn PlayerController>>>,
     current_player_index: usize,
-    game_over: bool,
+    history: Vec<MoveRecord>,
+    /// Moves taken back with `undo`, most recent last
+    undone: Vec<MoveRecord>,
 }
 
 impl Default for Game {
@@ -21,53 +30,73 @@ impl Default for Game {
             board: Board::new(),
             game_mode: GameMode::TwoPlayer,
             players: plrs,
-            game_over: false,
+            controllers: vec![None, None],
             current_player_index: 0,
+            history: Vec::new(),
+            undone: Vec::new(),
         }
     }
 }
 impl Game {
     /// Creates a new game with default values
-    pub(crate) fn new() -> Self {
+    pub fn new() -> Self {
         self::Game::default()
     }
+    /// Creates a new game played on `board` instead of the default 3x3 one
+    pub fn with_board(board: Board) -> Self {
+        Self {
+            board,
+            ..Self::default()
+        }
+    }
+    /// Creates a game from a position in `Board::to_notation` format,
+    /// with X as player 1 and O as player 2. The game has no move history;
+    /// `starting_position` gives the position back.
+    pub fn from_notation(notation: &str) -> GameResult<Self> {
+        let (board, to_move) = Board::from_notation(notation)?;
+        let mut game = Self::with_board(board);
+        if let Some(index) = game.players.iter().position(|p| p.symbol() == to_move) {
+            game.current_player_index = index;
+        }
+        Ok(game)
+    }
+    /// Writes the current position in `Board::to_notation` format
+    pub fn to_notation(&self) -> String {
+        self.board.to_notation(self.current_player_symbol())
+    }
     /// Gets the current game mode
-    pub(crate) fn game_mode(&self) -> &GameMode {
+    pub fn game_mode(&self) -> &GameMode {
         &self.game_mode
     }
     /// Sets the current game mode
-    pub(crate) fn set_game_mode(&mut self, game_mode: GameMode) {
+    pub fn set_game_mode(&mut self, game_mode: GameMode) {
         self.game_mode = game_mode;
     }
-    /// Gets the current board as a mutable reference
-    pub(crate) fn board_mut(&mut self) -> &mut Board {
-        &mut self.board
-    }
     /// Gets the current board as an immutable reference
-    pub(crate) fn board(&self) -> &Board {
+    pub fn board(&self) -> &Board {
         &self.board
     }
     /// Gets the current player
-    pub(crate) fn current_player(&self) -> Player {
+    pub fn current_player(&self) -> Player {
         self.players[self.current_player_index]
     }
     /// Gets the current player as a mutable reference
-    pub(crate) fn current_player_mut(&mut self) -> &mut Player {
+    pub fn current_player_mut(&mut self) -> &mut Player {
         &mut self.players[self.current_player_index]
     }
     /// Gets the current player's symbol
-    pub(crate) fn current_player_symbol(&self) -> PlayerSymbol {
+    pub fn current_player_symbol(&self) -> PlayerSymbol {
         self.current_player().symbol()
     }
     /// Gets all players as an immutable slice
     /// of length 2.
-
-    pub(crate) fn players(&self) -> &Vec<Player> {
+    pub fn players(&self) -> &[Player] {
         &self.players
     }
-    /// Gets all players as a mutable slice
-    /// Returns a mutable reference to the array of players
-    pub(crate) fn players_mut(&mut self) -> &mut Vec<Player> {
+    /// Gets all players as a mutable slice of length 2,
+    /// e.g. to change their symbols before the game starts.
+    /// There is always one controller slot per player, so none can be added.
+    pub fn players_mut(&mut self) -> &mut [Player] {
         &mut self.players
     }
 
@@ -75,27 +104,24 @@ impl Game {
     /// and sets the next player's symbol
     /// to the opposite of the current player's symbol
     /// E.g. X -> O, O -> X
-    pub(crate) fn set_player_symbol(&mut self, current_player: &mut Player, symbol: PlayerSymbol) -> AnyResult<(), Error>{
+    pub fn set_player_symbol(&mut self, current_player: &mut Player, symbol: PlayerSymbol) -> GameResult<()>{
         if current_player.symbol() == symbol {
-            bail!("Player already has that symbol")
+            return Err(GameError::InvalidSetup("Player already has that symbol".to_string()));
         }
         self.current_player_mut().set_symbol(symbol);
-        let opp = self.get_next_player_mut();
-        match opp {
-            Ok(opp) => opp.set_symbol(symbol.opposite()),
-            Err(e) => bail!(e),
-        };
+        let opp = self.get_next_player_mut()?;
+        opp.set_symbol(symbol.opposite());
         Ok(())
     }
     /// Gets the next player
     /// Cycle through the players using an iterator
     /// and return the next one
-    pub(crate) fn get_next_player_mut(&mut self) -> anyhow::Result<&mut Player, Error> {
+    pub fn get_next_player_mut(&mut self) -> GameResult<&mut Player> {
         // iterate through the players
         // if the current player is the last player in the vector,
         // then the next player is the first player in the vector
         if self.players.is_empty() {
-            bail!("No players available");
+            return Err(GameError::InvalidSetup("No players available".to_string()));
         }
         let mut cur_idx = self.current_player().get_player_number() as usize;
         cur_idx -= 1; // Subtract 1 because the player number is 1-based
@@ -105,23 +131,292 @@ impl Game {
         if let Some(player) = self.players_mut().get_mut(cur_idx) {
             Ok(player)
         } else {
-            bail!("could not get next player");
+            Err(GameError::InvalidSetup("could not get next player".to_string()))
         }
     }
-    /// Gets the next player
-    /// Cycle through the players using an iterator
-    /// and return the next one
-    pub(crate) fn get_next_player(&self) -> &Player {
-        let next_player = self.players.iter().cycle().skip(self.current_player_index + 1).next().unwrap();
-        next_player
+    /// Gets the next player, wrapping around
+    /// after the last one
+    pub fn get_next_player(&self) -> &Player {
+        &self.players[(self.current_player_index + 1) % self.players.len()]
     }
-    pub(crate) fn game_over(&self) -> bool {
-        self.game_over
+    /// Gets whether the game is still going, won or drawn
+    pub fn status(&self) -> &GameStatus {
+        self.board.status()
     }
-    pub(crate) fn set_game_over(&mut self, game_over: bool) {
-        self.game_over = game_over;
+    /// Checks whether someone has won or the board is full
+    pub fn game_over(&self) -> bool {
+        self.board.is_game_over()
     }
-    pub(crate) fn winner(&mut self) -> Option<Player> {
+    /// Gets the player who won, if anyone has
+    pub fn winner(&self) -> Option<Player> {
         self.board.get_winner()
     }
+    /// Gets the index of the player whose turn it is
+    pub fn current_player_index(&self) -> usize {
+        self.current_player_index
+    }
+    /// Gets the index of the player who made, or will make, the first move
+    pub fn first_player_index(&self) -> usize {
+        let first_mover = match self.history.first().or(self.undone.last()) {
+            Some(first_move) => first_move.player(),
+            None => return self.current_player_index,
+        };
+        self.players
+            .iter()
+            .position(|p| *p == first_mover)
+            .unwrap_or(self.current_player_index)
+    }
+    /// Makes the player at `player_index` move first.
+    /// Only allowed before any moves have been played.
+    pub fn set_first_player(&mut self, player_index: usize) -> GameResult<()> {
+        if !self.history.is_empty() || !self.undone.is_empty() {
+            return Err(GameError::InvalidSetup(
+                "The first player can't change once the game has started".to_string(),
+            ));
+        }
+        if player_index >= self.players.len() {
+            return Err(GameError::InvalidSetup(format!(
+                "There is no player {}",
+                player_index + 1
+            )));
+        }
+        self.current_player_index = player_index;
+        Ok(())
+    }
+    /// Hands the turn to the next player, wrapping around
+    /// after the last one
+    pub fn advance_to_next_player(&mut self) {
+        self.current_player_index = (self.current_player_index + 1) % self.players.len();
+    }
+    /// Attaches a controller to the player at `player_index`,
+    /// replacing any controller it had before
+    pub fn set_controller(
+        &mut self,
+        player_index: usize,
+        controller: Box<dyn PlayerController>,
+    ) -> GameResult<()> {
+        match self.controllers.get_mut(player_index) {
+            Some(slot) => {
+                *slot = Some(controller);
+                Ok(())
+            }
+            None => Err(GameError::NoController { player_index }),
+        }
+    }
+    /// Gets every move played so far, oldest first
+    pub fn history(&self) -> &[MoveRecord] {
+        &self.history
+    }
+    /// Gets the board as it was before the first move in the history:
+    /// empty, unless the game was set up from a position
+    pub fn starting_board(&self) -> Board {
+        let mut board = self.board.clone();
+        for record in &self.history {
+            let (x, y) = record.coords();
+            board
+                .clear(x, y)
+                .expect("recorded moves are on the board");
+        }
+        board
+    }
+    /// Gets the position the game was set up from, in `Board::to_notation`
+    /// format with the first player to move, or None if it started on an empty board
+    pub fn starting_position(&self) -> Option<String> {
+        let board = self.starting_board();
+        if board == board.empty_like() {
+            return None;
+        }
+        let first = self.players[self.first_player_index()].symbol();
+        Some(board.to_notation(first))
+    }
+    /// Checks whether the player at `player_index` is controlled by a person
+    pub fn is_human(&self, player_index: usize) -> bool {
+        match self.controllers.get(player_index) {
+            Some(Some(controller)) => controller.is_human(),
+            _ => false,
+        }
+    }
+    /// Places the current player's symbol at (x, y),
+    /// then either ends the game or passes the turn on.
+    /// Moves that were taken back can no longer be redone afterwards.
+    pub fn apply_move(&mut self, x: u8, y: u8) -> GameResult<()> {
+        self.place(x, y)?;
+        self.undone.clear();
+        Ok(())
+    }
+    /// Like `apply_move`, but first checks that it is `symbol`'s turn
+    pub fn apply_move_for(&mut self, symbol: PlayerSymbol, x: u8, y: u8) -> GameResult<()> {
+        if self.game_over() {
+            return Err(GameError::GameOver);
+        }
+        if symbol != self.current_player_symbol() {
+            return Err(GameError::WrongTurn {
+                expected: self.current_player_symbol(),
+                found: symbol,
+            });
+        }
+        self.apply_move(x, y)
+    }
+    /// Takes back the last move, giving the turn back to whoever made it
+    pub fn undo(&mut self) -> GameResult<MoveRecord> {
+        let last_move = match self.history.pop() {
+            Some(last_move) => last_move,
+            None => return Err(GameError::NothingToUndo),
+        };
+        let (x, y) = last_move.coords();
+        self.board.clear(x, y)?;
+        if let Some(index) = self.players.iter().position(|p| *p == last_move.player()) {
+            self.current_player_index = index;
+        }
+        self.undone.push(last_move);
+        Ok(last_move)
+    }
+    /// Plays the last move that was taken back again
+    pub fn redo(&mut self) -> GameResult<MoveRecord> {
+        let next_move = match self.undone.last() {
+            Some(next_move) => *next_move,
+            None => return Err(GameError::NothingToRedo),
+        };
+        let (x, y) = next_move.coords();
+        self.place(x, y)?;
+        self.undone.pop();
+        Ok(next_move)
+    }
+    /// Takes back every move so the game can be played again from the start,
+    /// keeping the board size, players and controllers
+    pub fn restart(&mut self) {
+        while self.undo().is_ok() {}
+        self.undone.clear();
+    }
+    /// Places the current player's symbol at (x, y) and records the move
+    fn place(&mut self, x: u8, y: u8) -> GameResult<()> {
+        if self.game_over() {
+            return Err(GameError::GameOver);
+        }
+        let player = self.current_player();
+        self.board.set(x, y, player)?;
+        self.history
+            .push(MoveRecord::new(player, x, y, self.history.len() + 1));
+        if !self.game_over() {
+            self.advance_to_next_player();
+        }
+        Ok(())
+    }
+    /// Plays one turn: asks the current player's controller for an action
+    /// and, if it is a move, validates and applies it to the board.
+    /// If the move ends the game, every controller is told about it.
+    pub fn play_turn(&mut self) -> GameResult<PlayerAction> {
+        if self.game_over() {
+            return Err(GameError::GameOver);
+        }
+        let index = self.current_player_index;
+        // Take the controller out while it runs so it can look at the game
+        let mut controller = self.controllers[index]
+            .take()
+            .ok_or(GameError::NoController {
+                player_index: index,
+            })?;
+        let action = controller.choose_action(self);
+        self.controllers[index] = Some(controller);
+        let action = action?;
+        if let PlayerAction::Move(x, y) = action {
+            self.apply_move(x, y)?;
+            if self.game_over() {
+                self.finish()?;
+            }
+        }
+        Ok(action)
+    }
+    /// Tells every controller that the game has finished
+    fn finish(&mut self) -> GameResult<()> {
+        for index in 0..self.controllers.len() {
+            if let Some(mut controller) = self.controllers[index].take() {
+                let result = controller.game_finished(self);
+                self.controllers[index] = Some(controller);
+                result?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays `moves` in order from a new game
+    fn played(moves: &[(u8, u8)]) -> Game {
+        let mut game = Game::new();
+        for &(x, y) in moves {
+            game.apply_move(x, y).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn undoing_a_winning_move_carries_the_game_on() {
+        // X wins along the top row
+        let mut game = played(&[(0, 0), (0, 1), (1, 0), (1, 1), (2, 0)]);
+        assert!(game.game_over());
+        let undone = game.undo().unwrap();
+        assert_eq!(undone.coords(), (2, 0));
+        assert_eq!(*game.status(), GameStatus::InProgress);
+        assert_eq!(game.current_player_symbol(), PlayerSymbol::Cross);
+        assert_eq!(game.board().get(2, 0), None);
+        game.redo().unwrap();
+        assert_eq!(
+            game.winner().map(|winner| winner.symbol()),
+            Some(PlayerSymbol::Cross)
+        );
+    }
+
+    #[test]
+    fn a_new_move_after_undo_clears_the_redo_list() {
+        let mut game = played(&[(0, 0), (1, 1)]);
+        game.undo().unwrap();
+        game.apply_move(2, 2).unwrap();
+        assert!(matches!(game.redo(), Err(GameError::NothingToRedo)));
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(game.board().get(1, 1), None);
+    }
+
+    #[test]
+    fn undo_and_redo_fail_with_nothing_to_do() {
+        let mut game = Game::new();
+        assert!(matches!(game.undo(), Err(GameError::NothingToUndo)));
+        assert!(matches!(game.redo(), Err(GameError::NothingToRedo)));
+    }
+
+    #[test]
+    fn remembers_who_moved_first_after_undoing_everything() {
+        let mut game = Game::new();
+        game.set_first_player(1).unwrap();
+        for (x, y) in [(0, 0), (1, 1), (2, 2)] {
+            game.apply_move(x, y).unwrap();
+        }
+        while game.undo().is_ok() {}
+        assert_eq!(game.first_player_index(), 1);
+        assert_eq!(game.current_player_index(), 1);
+        // The first player can't change while the moves can still be redone
+        assert!(game.set_first_player(0).is_err());
+        game.redo().unwrap();
+        assert_eq!(game.history()[0].player().symbol(), PlayerSymbol::Nought);
+    }
+
+    #[test]
+    fn restart_empties_the_board_and_forgets_the_moves() {
+        let mut game = Game::new();
+        game.set_first_player(1).unwrap();
+        for (x, y) in [(0, 0), (1, 1), (2, 2)] {
+            game.apply_move(x, y).unwrap();
+        }
+        game.restart();
+        assert_eq!(game.board().empty_cells().len(), 9);
+        assert!(game.history().is_empty());
+        assert!(matches!(game.redo(), Err(GameError::NothingToRedo)));
+        assert_eq!(game.first_player_index(), 1);
+        assert_eq!(game.current_player_index(), 1);
+        // A new game can be started by the other player
+        game.set_first_player(0).unwrap();
+    }
 }
@@ -0,0 +1,144 @@
+use crate::modules::{input_error::InputError, player_symbol::PlayerSymbol};
+
+use std::{fmt::Display, io};
+
+/// Result of anything in the game that can fail with a `GameError`
+pub type GameResult<T> = Result<T, GameError>;
+
+/// Everything that can go wrong while setting up, playing,
+/// reading or writing a game, so callers can react to each case
+#[derive(Debug)]
+pub enum GameError {
+    /// Something typed at the terminal couldn't be understood
+    Input(InputError),
+    /// Text from a file, notation or option couldn't be parsed
+    Parse(String),
+    /// A board or game was set up with impossible settings,
+    /// e.g. a win length longer than the board
+    InvalidSetup(String),
+    /// A position that can't come up in a real game
+    ImpossiblePosition(String),
+    /// 0-based coordinates that are off the board
+    OutOfRange { x: u8, y: u8 },
+    /// The 0-based cell already has a symbol in it
+    CellOccupied { x: u8, y: u8 },
+    /// A move was tried after the game had finished
+    GameOver,
+    /// A move was made by the player whose turn it isn't
+    WrongTurn {
+        expected: PlayerSymbol,
+        found: PlayerSymbol,
+    },
+    /// There are no moves to take back
+    NothingToUndo,
+    /// There are no taken back moves to play again
+    NothingToRedo,
+    /// A replay was asked to jump past the end of its game
+    NoSuchMove { ply: usize, move_count: usize },
+    /// The player at this index has no controller to pick its moves
+    NoController { player_index: usize },
+    /// A controller had no move to give, e.g. a script that has run out
+    NoMovesLeft,
+    /// Reading or writing a file failed
+    Io(io::Error),
+    /// Something went wrong on a 1-based line of a file
+    AtLine { line: usize, error: Box<GameError> },
+    /// Something went wrong with a 1-based move of a recorded game
+    AtMove {
+        move_number: usize,
+        error: Box<GameError>,
+    },
+}
+
+impl GameError {
+    /// Wraps the error with the line of the file it came from
+    pub fn at_line(self, line: usize) -> Self {
+        GameError::AtLine {
+            line,
+            error: Box::new(self),
+        }
+    }
+    /// Wraps the error with the move of a recorded game it came from
+    pub fn at_move(self, move_number: usize) -> Self {
+        GameError::AtMove {
+            move_number,
+            error: Box::new(self),
+        }
+    }
+    /// Gets the error underneath any line or move numbers
+    pub fn root(&self) -> &GameError {
+        match self {
+            GameError::AtLine { error, .. } | GameError::AtMove { error, .. } => error.root(),
+            _ => self,
+        }
+    }
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameError::Input(e) => write!(f, "{}", e),
+            GameError::Parse(message)
+            | GameError::InvalidSetup(message)
+            | GameError::ImpossiblePosition(message) => write!(f, "{}", message),
+            // Widened so the last coordinate a u8 can hold still shows as 1-based
+            GameError::OutOfRange { x, y } => write!(
+                f,
+                "{},{} is off the board",
+                u16::from(*x) + 1,
+                u16::from(*y) + 1
+            ),
+            GameError::CellOccupied { x, y } => write!(
+                f,
+                "{},{} is already taken",
+                u16::from(*x) + 1,
+                u16::from(*y) + 1
+            ),
+            GameError::GameOver => write!(f, "The game is already over"),
+            GameError::WrongTurn { expected, found } => {
+                write!(f, "It is {}'s turn, not {}'s", expected, found)
+            }
+            GameError::NothingToUndo => write!(f, "There are no moves to undo"),
+            GameError::NothingToRedo => write!(f, "There are no moves to redo"),
+            GameError::NoSuchMove { ply, move_count } => write!(
+                f,
+                "The game only has {} moves, so there is no move {}",
+                move_count, ply
+            ),
+            GameError::NoController { player_index } => {
+                write!(f, "Player {} has no controller", player_index + 1)
+            }
+            GameError::NoMovesLeft => write!(f, "There are no moves left to play"),
+            GameError::Io(e) => write!(f, "{}", e),
+            GameError::AtLine { line, error } => write!(f, "Line {}: {}", line, error),
+            GameError::AtMove { move_number, error } => {
+                write!(f, "Move {}: {}", move_number, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<InputError> for GameError {
+    fn from(e: InputError) -> Self {
+        GameError::Input(e)
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(e: io::Error) -> Self {
+        GameError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_the_last_coordinates_without_overflowing() {
+        let error = GameError::OutOfRange { x: 255, y: 0 };
+        assert_eq!(error.to_string(), "256,1 is off the board");
+    }
+}
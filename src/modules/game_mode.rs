@@ -1,4 +1,47 @@
-pub(crate) enum GameMode {
+use crate::modules::game_error::GameError;
+
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum GameMode {
     SinglePlayer,
     TwoPlayer,
+    /// Both players are AIs
+    ZeroPlayer,
+}
+
+impl GameMode {
+    /// Checks whether the player at `player_index` is played by the AI in this mode.
+    /// In single player mode that is player 2.
+    pub fn is_ai(&self, player_index: usize) -> bool {
+        match self {
+            GameMode::SinglePlayer => player_index == 1,
+            GameMode::TwoPlayer => false,
+            GameMode::ZeroPlayer => true,
+        }
+    }
+}
+
+impl Display for GameMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            GameMode::SinglePlayer => "single",
+            GameMode::TwoPlayer => "two",
+            GameMode::ZeroPlayer => "zero",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "single" => Ok(GameMode::SinglePlayer),
+            "two" => Ok(GameMode::TwoPlayer),
+            "zero" => Ok(GameMode::ZeroPlayer),
+            _ => Err(GameError::Parse(format!("Unknown game mode: {}", s))),
+        }
+    }
 }
@@ -0,0 +1,493 @@
+//! Game records: a PGN-like text format for keeping finished games, e.g.
+//!
+//! ```text
+//! [Event "Club night"]
+//! [Date "2026.10.17"]
+//! [X "Alice"]
+//! [O "Bob"]
+//! [First "X"]
+//! [Variant "standard"]
+//! [Board "3x3"]
+//! [WinLength "3"]
+//! [TimeControl "-"]
+//! [Result "1-0"]
+//!
+//! 1. 2,2 1,2 2. 1,1 3,1 3. 3,3 1-0
+//! ```
+//!
+//! Tags come first, then the moves as 1-based x,y numbered in pairs,
+//! ending with the result: `1-0` if X won, `0-1` if O won,
+//! `1/2-1/2` for a draw or `*` if the game wasn't finished.
+//! A game set up from a position rather than an empty board has a
+//! `Setup` tag with the position in `Board::to_notation` format.
+//! A file can hold any number of games one after another.
+
+use crate::modules::{
+    board::Board,
+    game::Game,
+    game_error::{GameError, GameResult},
+    game_status::GameStatus,
+    move_record::parse_coords,
+    player_symbol::PlayerSymbol,
+    variant::Variant,
+};
+
+use std::{
+    fmt::Display,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Results that can end a move list
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Longest line of moves written before wrapping
+const LINE_WIDTH: usize = 79;
+
+/// A recorded game: its tags and the moves that were played
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameRecord {
+    /// Tag names and values, in the order they are written
+    tags: Vec<(String, String)>,
+    /// 0-based coordinates of each move, in the order they were played
+    moves: Vec<(u8, u8)>,
+}
+
+impl GameRecord {
+    /// Records `game` with the standard tags filled in from it.
+    /// Names, event and time control are left as `?` or `-` to be set later.
+    pub fn from_game(game: &Game) -> Self {
+        let board = game.board();
+        let first = game.players()[game.first_player_index()].symbol();
+        let mut record = Self {
+            tags: Vec::new(),
+            moves: game
+                .history()
+                .iter()
+                .map(|record| record.coords())
+                .collect(),
+        };
+        record.set_tag("Event", "?");
+        record.set_tag("Date", &today());
+        record.set_tag("X", "?");
+        record.set_tag("O", "?");
+        record.set_tag("First", &first.to_string());
+        record.set_tag("Variant", &variant_name(board));
+        record.set_tag("Board", &format!("{}x{}", board.width(), board.height()));
+        record.set_tag("WinLength", &board.win_length().to_string());
+        if let Some(position) = game.starting_position() {
+            record.set_tag("Setup", &position);
+        }
+        record.set_tag("TimeControl", "-");
+        record.set_tag("Result", result_of(game));
+        record
+    }
+    /// Gets the value of a tag, if the record has it
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+    /// Sets a tag, adding it at the end if the record doesn't have it yet
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old_value)) => *old_value = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+    /// Gets every tag in order
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+    /// Gets the 0-based coordinates of every move in order
+    pub fn moves(&self) -> &[(u8, u8)] {
+        &self.moves
+    }
+    /// Gets the result written at the end of the moves
+    pub fn result(&self) -> &str {
+        self.tag("Result").unwrap_or("*")
+    }
+
+    /// Replays the record into a new game,
+    /// checking every move is legal and the result matches
+    pub fn to_game(&self) -> GameResult<Game> {
+        let board = match self.tag("Board") {
+            Some(size) => {
+                let (width, height) = size.split_once('x').ok_or_else(|| {
+                    GameError::Parse(format!("Board should look like 3x3, not '{}'", size))
+                })?;
+                let width = parse_size(width, "board width")?;
+                let height = parse_size(height, "board height")?;
+                let win_length = match self.tag("WinLength") {
+                    Some(win_length) => parse_size(win_length, "win length")?,
+                    None => width.min(height),
+                };
+                Board::with_size(width, height, win_length)?
+            }
+            None => Board::new(),
+        };
+        let mut first = self
+            .tag("First")
+            .map(|first| first.parse::<PlayerSymbol>())
+            .transpose()?;
+        let board = match self.tag("Setup") {
+            Some(setup) => {
+                let (start, to_move) = Board::from_notation(setup)?;
+                let size = |board: &Board| (board.width(), board.height(), board.win_length());
+                if self.tag("Board").is_some() && size(&start) != size(&board) {
+                    return Err(GameError::Parse(
+                        "The Setup position doesn't match the Board and WinLength tags".to_string(),
+                    ));
+                }
+                if let Some(first) = first.filter(|&first| first != to_move) {
+                    return Err(GameError::Parse(format!(
+                        "First says {} but the Setup position has {} to move",
+                        first, to_move
+                    )));
+                }
+                first = Some(to_move);
+                start
+            }
+            None => board,
+        };
+        let mut game = Game::with_board(board);
+        if let Some(first) = first {
+            game.players_mut()[0].set_symbol(first);
+            game.players_mut()[1].set_symbol(first.opposite());
+        }
+        for (index, &(x, y)) in self.moves.iter().enumerate() {
+            game.apply_move(x, y).map_err(|e| e.at_move(index + 1))?;
+        }
+        let actual = result_of(&game);
+        if self.result() != actual {
+            return Err(GameError::Parse(format!(
+                "The result says {} but the moves end in {}",
+                self.result(),
+                actual
+            )));
+        }
+        Ok(game)
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f)?;
+        let mut tokens = Vec::new();
+        for (index, (x, y)) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                tokens.push(format!("{}.", index / 2 + 1));
+            }
+            tokens.push(format!("{},{}", x + 1, y + 1));
+        }
+        tokens.push(self.result().to_string());
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                writeln!(f, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{}", line)
+    }
+}
+
+/// Reads every game in a record file
+pub fn read_records(path: impl AsRef<Path>) -> GameResult<Vec<GameRecord>> {
+    let text = fs::read_to_string(path)?;
+    parse_records(&text)
+}
+
+/// Adds a game to the end of a record file, creating it if needed
+pub fn append_record(path: impl AsRef<Path>, record: &GameRecord) -> GameResult<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    // Keep a blank line between games
+    let separator = if file.metadata()?.len() > 0 { "\n" } else { "" };
+    write!(file, "{}{}", separator, record)?;
+    Ok(())
+}
+
+/// Writes games to a record file, replacing anything already there
+pub fn write_records(path: impl AsRef<Path>, records: &[GameRecord]) -> GameResult<()> {
+    let text = records
+        .iter()
+        .map(|record| record.to_string())
+        .collect::<Vec<String>>()
+        .join("\n");
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Parses every game in `text`
+pub fn parse_records(text: &str) -> GameResult<Vec<GameRecord>> {
+    let mut records = Vec::new();
+    let mut tags = Vec::new();
+    let mut moves = Vec::new();
+    // Whether the current game's moves have begun, so a result is needed
+    let mut in_moves = false;
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            if in_moves {
+                return Err(no_result(records.len() + 1).at_line(line_number));
+            }
+            tags.push(parse_tag(line).map_err(|e| e.at_line(line_number))?);
+            continue;
+        }
+        in_moves = true;
+        for token in line.split_whitespace() {
+            if RESULTS.contains(&token) {
+                let mut record = GameRecord {
+                    tags: std::mem::take(&mut tags),
+                    moves: std::mem::take(&mut moves),
+                };
+                if record.tag("Result").is_some_and(|result| result != token) {
+                    return Err(GameError::Parse(format!(
+                        "Game {} ends in {} but its Result tag says {}",
+                        records.len() + 1,
+                        token,
+                        record.result()
+                    ))
+                    .at_line(line_number));
+                }
+                record.set_tag("Result", token);
+                records.push(record);
+                in_moves = false;
+            } else if token.ends_with('.') && token[..token.len() - 1].parse::<usize>().is_ok() {
+                // Move numbers are only there for people to read
+            } else {
+                moves.push(parse_coords(token).map_err(|e| e.at_line(line_number))?);
+            }
+        }
+    }
+    if in_moves || !tags.is_empty() {
+        return Err(no_result(records.len() + 1));
+    }
+    Ok(records)
+}
+
+/// Error for a game whose moves don't end with a result
+fn no_result(game_number: usize) -> GameError {
+    GameError::Parse(format!(
+        "Game {} has no result at the end of its moves",
+        game_number
+    ))
+}
+
+/// Parses a board size or win length tag
+fn parse_size(value: &str, name: &str) -> GameResult<u8> {
+    value
+        .parse::<u8>()
+        .map_err(|_| GameError::Parse(format!("Invalid {} '{}'", name, value)))
+}
+
+/// Parses a `[Name "value"]` tag line
+fn parse_tag(line: &str) -> GameResult<(String, String)> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(|| GameError::Parse("Tags must look like [Name \"value\"]".to_string()))?;
+    let (name, value) = inner
+        .split_once(' ')
+        .ok_or_else(|| GameError::Parse(format!("Tag '{}' has no value", inner)))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(|| GameError::Parse(format!("The value of tag {} must be in quotes", name)))?;
+    Ok((name.to_string(), unescape(value)))
+}
+
+/// Escapes quotes and backslashes in a tag value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reverses `escape`
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(escaped) = chars.next() {
+                unescaped.push(escaped);
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Gets the result token for where `game` has got to
+fn result_of(game: &Game) -> &'static str {
+    match game.status() {
+        GameStatus::Won { winner, .. } => match winner.symbol() {
+            PlayerSymbol::Cross => "1-0",
+            PlayerSymbol::Nought => "0-1",
+        },
+        GameStatus::Draw => "1/2-1/2",
+        GameStatus::InProgress => "*",
+    }
+}
+
+/// Names the variant being played on `board`
+fn variant_name(board: &Board) -> String {
+    match Variant::of(board) {
+        Some(variant) => variant.to_string(),
+        None => "m,n,k".to_string(),
+    }
+}
+
+/// Gets today's date (UTC) as `YYYY.MM.DD`
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or_default() as i64;
+    // Convert days since 1970-01-01 into a calendar date
+    // (Howard Hinnant's civil_from_days)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDS: &str = "\
+[Event \"Club night\"]
+[X \"Alice \\\"Ace\\\"\"]
+[O \"Bob\"]
+[Result \"1-0\"]
+
+1. 2,2 1,2 2. 1,1 3,1 3. 3,3 1-0
+
+[Event \"Rematch\"]
+[Result \"*\"]
+
+1. 1,1 *
+";
+
+    #[test]
+    fn round_trips_records() {
+        let records = parse_records(RECORDS).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].tag("X"), Some("Alice \"Ace\""));
+        assert_eq!(records[0].moves(), [(1, 1), (0, 1), (0, 0), (2, 0), (2, 2)]);
+        assert_eq!(records[1].result(), "*");
+        let written = records
+            .iter()
+            .map(|record| record.to_string())
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(written, RECORDS);
+    }
+
+    #[test]
+    fn round_trips_a_played_game() {
+        let mut game = Game::new();
+        for (x, y) in [
+            (1, 1),
+            (0, 0),
+            (2, 2),
+            (0, 2),
+            (0, 1),
+            (2, 1),
+            (1, 0),
+            (1, 2),
+            (2, 0),
+        ] {
+            game.apply_move(x, y).unwrap();
+        }
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.result(), "1/2-1/2");
+        let parsed = parse_records(&record.to_string()).unwrap();
+        assert_eq!(parsed, [record]);
+        let replayed = parsed[0].to_game().unwrap();
+        assert_eq!(replayed.board(), game.board());
+    }
+
+    #[test]
+    fn round_trips_a_game_set_up_from_a_position() {
+        let mut game = Game::from_notation("X../.O./... x").unwrap();
+        game.apply_move(2, 0).unwrap();
+        let record = GameRecord::from_game(&game);
+        assert_eq!(record.tag("Setup"), Some("X../.O./... x"));
+        assert_eq!(record.moves(), [(2, 0)]);
+        let parsed = parse_records(&record.to_string()).unwrap();
+        let replayed = parsed[0].to_game().unwrap();
+        assert_eq!(replayed.board(), game.board());
+        assert_eq!(replayed.starting_position(), game.starting_position());
+    }
+
+    #[test]
+    fn rejects_a_setup_that_does_not_match_the_tags() {
+        let wrong_size = "[Board \"4x4\"]\n[Setup \"X../.O./... x\"]\n\n*\n";
+        assert!(parse_records(wrong_size).unwrap()[0].to_game().is_err());
+        let wrong_first = "[First \"O\"]\n[Setup \"X../.O./... x\"]\n\n*\n";
+        assert!(parse_records(wrong_first).unwrap()[0].to_game().is_err());
+    }
+
+    #[test]
+    fn wraps_long_move_lists() {
+        let mut game = Game::with_board(Board::with_size(15, 15, 5).unwrap());
+        for index in 0..40 {
+            game.apply_move(index % 15, index / 15 * 2 + index % 2)
+                .unwrap();
+        }
+        let text = GameRecord::from_game(&game).to_string();
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        let parsed = parse_records(&text).unwrap();
+        assert_eq!(parsed[0].moves().len(), 40);
+    }
+
+    #[test]
+    fn rejects_a_result_that_does_not_match_the_moves() {
+        let records = parse_records("1. 2,2 1,2 1-0\n").unwrap();
+        assert!(records[0].to_game().is_err());
+        assert!(parse_records("[Result \"0-1\"]\n\n1. 2,2 1-0\n").is_err());
+    }
+
+    #[test]
+    fn rejects_a_game_without_a_result() {
+        assert!(parse_records("1. 2,2 1,2\n").is_err());
+        assert!(parse_records("1. 2,2\n[Event \"Next\"]\n").is_err());
+    }
+
+    #[test]
+    fn rejects_illegal_moves_on_replay() {
+        let records = parse_records("1. 2,2 2,2 *\n").unwrap();
+        assert!(matches!(
+            records[0].to_game(),
+            Err(GameError::AtMove { move_number: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+use crate::modules::{
+    bitboard::BitBoard,
+    board::Board,
+    game::Game,
+    game_error::{GameError, GameResult},
+    player::Player,
+    player_symbol::PlayerSymbol,
+    random::Rng,
+};
+
+/// The rules of a game as a search sees them: whose turn it is,
+/// which moves are legal, what playing one does and how the game ended.
+/// Engines written against this work for any rules that implement it.
+pub trait GameState: Clone {
+    /// Gets the symbol of the player whose turn it is
+    fn to_move(&self) -> PlayerSymbol;
+    /// Gets every move the player to move can make, none once the game is over
+    fn legal_moves(&self) -> Vec<(u8, u8)>;
+    /// Plays a move for the player to move and passes the turn on
+    fn apply(&mut self, x: u8, y: u8) -> GameResult<()>;
+    /// Checks whether the game has finished
+    fn is_over(&self) -> bool;
+    /// Gets the symbol of the player who won, or None for a draw or a game in progress
+    fn winner(&self) -> Option<PlayerSymbol>;
+    /// Plays random legal moves until the game is over.
+    /// Rules that can do this faster than asking for the legal moves
+    /// before every move should override it.
+    fn play_out(&mut self, rng: &mut Rng) {
+        while let Some(&(x, y)) = rng.choose(&self.legal_moves()) {
+            self.apply(x, y).expect("legal moves can be applied");
+        }
+    }
+}
+
+/// A board and whose turn it is, without a game's history or controllers,
+/// so it is cheap to copy
+#[derive(Clone, Debug)]
+pub struct Position {
+    board: Board,
+    /// The player to move, then their opponent
+    players: [Player; 2],
+}
+
+impl Position {
+    /// Creates a position on `board` with `to_move` to play next
+    pub fn new(board: Board, to_move: Player, opponent: Player) -> Self {
+        Self {
+            board,
+            players: [to_move, opponent],
+        }
+    }
+    /// Copies the current position of `game`
+    pub fn from_game(game: &Game) -> Self {
+        Self::new(
+            game.board().clone(),
+            game.current_player(),
+            *game.get_next_player(),
+        )
+    }
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+}
+
+impl GameState for Position {
+    fn to_move(&self) -> PlayerSymbol {
+        self.players[0].symbol()
+    }
+    fn legal_moves(&self) -> Vec<(u8, u8)> {
+        if self.board.is_game_over() {
+            Vec::new()
+        } else {
+            self.board.empty_cells()
+        }
+    }
+    fn apply(&mut self, x: u8, y: u8) -> GameResult<()> {
+        if self.board.is_game_over() {
+            return Err(GameError::GameOver);
+        }
+        self.board.set(x, y, self.players[0])?;
+        self.players.swap(0, 1);
+        Ok(())
+    }
+    fn is_over(&self) -> bool {
+        self.board.is_game_over()
+    }
+    fn winner(&self) -> Option<PlayerSymbol> {
+        self.board.get_winner().map(|winner| winner.symbol())
+    }
+    fn play_out(&mut self, rng: &mut Rng) {
+        // Playing a cell only ever takes it off the list,
+        // so the empty cells only need finding once
+        let mut moves = self.legal_moves();
+        while !self.is_over() && !moves.is_empty() {
+            let (x, y) = moves.swap_remove(rng.below(moves.len()));
+            self.apply(x, y).expect("empty cells can be played on");
+        }
+    }
+}
+
+/// The same as `Position` on a `BitBoard`, so playouts don't allocate
+/// or look along whole rows for a win. Only fits boards of up to
+/// `BitBoard::MAX_CELLS` cells.
+#[derive(Clone, Debug)]
+pub struct BitPosition {
+    board: BitBoard,
+    to_move: PlayerSymbol,
+    /// Kept up to date as moves are played, so it never has to be looked for
+    winner: Option<PlayerSymbol>,
+}
+
+impl BitPosition {
+    /// Copies the current position of `game`.
+    /// Fails if the board is too big for a bitboard.
+    pub fn from_game(game: &Game) -> GameResult<Self> {
+        Ok(Self {
+            board: BitBoard::from_board(game.board())?,
+            to_move: game.current_player_symbol(),
+            winner: game.board().get_winner().map(|winner| winner.symbol()),
+        })
+    }
+    pub fn board(&self) -> &BitBoard {
+        &self.board
+    }
+    /// Plays `cell`, which must be empty, for the player to move
+    fn play(&mut self, cell: u8) {
+        self.board.make_move(cell, self.to_move);
+        if self.board.wins_through(cell, self.to_move) {
+            self.winner = Some(self.to_move);
+        }
+        self.to_move = self.to_move.opposite();
+    }
+}
+
+impl GameState for BitPosition {
+    fn to_move(&self) -> PlayerSymbol {
+        self.to_move
+    }
+    fn legal_moves(&self) -> Vec<(u8, u8)> {
+        if self.is_over() {
+            Vec::new()
+        } else {
+            self.board
+                .moves()
+                .map(|cell| self.board.coords(cell))
+                .collect()
+        }
+    }
+    fn apply(&mut self, x: u8, y: u8) -> GameResult<()> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if x >= self.board.width() || y >= self.board.height() {
+            return Err(GameError::OutOfRange { x, y });
+        }
+        let cell = self.board.cell(x, y);
+        if self.board.get(cell).is_some() {
+            return Err(GameError::CellOccupied { x, y });
+        }
+        self.play(cell);
+        Ok(())
+    }
+    fn is_over(&self) -> bool {
+        self.winner.is_some() || self.board.is_full()
+    }
+    fn winner(&self) -> Option<PlayerSymbol> {
+        self.winner
+    }
+    fn play_out(&mut self, rng: &mut Rng) {
+        // Picks moves the same way as `Position::play_out`,
+        // so both play out the same game from the same position
+        let mut cells = if self.is_over() {
+            Vec::new()
+        } else {
+            self.board.moves().collect::<Vec<_>>()
+        };
+        while !self.is_over() && !cells.is_empty() {
+            let cell = cells.swap_remove(rng.below(cells.len()));
+            self.play(cell);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bit_position_plays_out_like_position() {
+        for notation in [
+            ".../.../... x",
+            "X../.O./... x",
+            "XX./OO./... o",
+            "..../..../..../.... x",
+        ] {
+            let game = Game::from_notation(notation).unwrap();
+            for seed in 0..20 {
+                let mut position = Position::from_game(&game);
+                let mut bit_position = BitPosition::from_game(&game).unwrap();
+                assert_eq!(bit_position.legal_moves(), position.legal_moves());
+                position.play_out(&mut Rng::new(seed));
+                bit_position.play_out(&mut Rng::new(seed));
+                assert_eq!(
+                    bit_position.winner(),
+                    position.winner(),
+                    "{} seed {}",
+                    notation,
+                    seed
+                );
+                assert_eq!(bit_position.board().hash(), position.board().hash());
+            }
+        }
+    }
+
+    #[test]
+    fn bit_position_rejects_illegal_moves() {
+        let game = Game::from_notation("XX./OO./... x").unwrap();
+        let mut position = BitPosition::from_game(&game).unwrap();
+        assert!(matches!(
+            position.apply(0, 0),
+            Err(GameError::CellOccupied { .. })
+        ));
+        assert!(matches!(
+            position.apply(3, 0),
+            Err(GameError::OutOfRange { .. })
+        ));
+        position.apply(2, 0).unwrap();
+        assert_eq!(position.winner(), Some(PlayerSymbol::Cross));
+        assert!(position.legal_moves().is_empty());
+        assert!(matches!(position.apply(2, 2), Err(GameError::GameOver)));
+    }
+
+    #[test]
+    fn boards_too_big_for_a_bitboard_are_refused() {
+        let game =
+            Game::from_notation(&format!("{} x", vec![".".repeat(12); 12].join("/"))).unwrap();
+        assert!(BitPosition::from_game(&game).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use crate::modules::player::Player;
+
+use std::fmt::Display;
+
+/// Where a game has got to: still going, won or drawn
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum GameStatus {
+    #[default]
+    InProgress,
+    /// `line` holds the cells of the winning line, from one end to the other
+    Won { winner: Player, line: Vec<(u8, u8)> },
+    /// The board filled up without anyone winning
+    Draw,
+}
+
+impl Display for GameStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameStatus::InProgress => write!(f, "In progress"),
+            GameStatus::Won { winner, .. } => write!(f, "{} won", winner),
+            GameStatus::Draw => write!(f, "Draw"),
+        }
+    }
+}
+
+impl GameStatus {
+    /// Checks whether the game has finished, either way
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::InProgress
+    }
+    /// Gets the player who won, if anyone has
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameStatus::Won { winner, .. } => Some(*winner),
+            _ => None,
+        }
+    }
+    /// Gets the cells of the winning line, if anyone has won
+    pub fn winning_line(&self) -> Option<&[(u8, u8)]> {
+        match self {
+            GameStatus::Won { line, .. } => Some(line),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::modules::{board::Board, engine::Engine, game::Game, player::Player};
+
+/// Beatable opponent that only looks one move ahead:
+/// it wins if it can, blocks an immediate loss,
+/// and otherwise prefers the centre, then corners, then edges
+#[derive(Default)]
+pub struct GreedyEngine {}
+
+impl GreedyEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Engine for GreedyEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        let board = game.board();
+        let player = game.current_player();
+        let opponent = *game.get_next_player();
+        let moves = board.empty_cells();
+        if let Some(&winning_move) = moves.iter().find(|&&(x, y)| wins_with(board, x, y, player)) {
+            return Some(winning_move);
+        }
+        if let Some(&blocking_move) = moves
+            .iter()
+            .find(|&&(x, y)| wins_with(board, x, y, opponent))
+        {
+            return Some(blocking_move);
+        }
+        moves
+            .into_iter()
+            .min_by_key(|&(x, y)| position_rank(board, x, y))
+    }
+}
+
+/// Checks whether `player` would win by playing at (x, y)
+fn wins_with(board: &Board, x: u8, y: u8, player: Player) -> bool {
+    let mut child = board.clone();
+    child.set(x, y, player).is_ok() && child.get_winner() == Some(player)
+}
+
+/// Ranks a cell by how much the greedy engine wants it, lower is better:
+/// 0 for the centre, 1 for a corner and 2 for anything else
+fn position_rank(board: &Board, x: u8, y: u8) -> u8 {
+    let last_x = board.width() - 1;
+    let last_y = board.height() - 1;
+    let is_centre = |value: u8, last: u8| value == last / 2 || value == last.div_ceil(2);
+    if is_centre(x, last_x) && is_centre(y, last_y) {
+        0
+    } else if (x == 0 || x == last_x) && (y == 0 || y == last_y) {
+        1
+    } else {
+        2
+    }
+}
@@ -1,23 +1,12 @@
 use std::fmt::Display;
 
-use anyhow::anyhow;
-
-#[derive(Debug)]
-pub(crate) enum InputError {
+/// Problems with what was typed at the terminal
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum InputError {
     InvalidInput,
     InvalidCoordinates,
 }
 
-// Compare InputError with anyhow::Error
-impl From<InputError> for anyhow::Error {
-    fn from(val: InputError) -> Self {
-        match val {
-            InputError::InvalidInput => anyhow!("Invalid input"),
-            InputError::InvalidCoordinates => anyhow!("Invalid coordinates"),
-        }
-    }
-}
-
 impl Display for InputError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,3 +15,5 @@ impl Display for InputError {
         }
     }
 }
+
+impl std::error::Error for InputError {}
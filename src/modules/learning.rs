@@ -0,0 +1,381 @@
+//! An opponent that learns: it keeps a value for each position a move can
+//! lead to and plays the move with the best one. After every game each
+//! value it reached is moved towards the best value it could reach on its
+//! next turn, or towards the result if there wasn't one (Q-learning, with
+//! the positions after a move standing in for moves).
+//!
+//! The table is saved one position per line, sorted by notation:
+//!
+//! ```text
+//! .../.X./... o 0.6
+//! ```
+//!
+//! That is the position after a move in `Board::to_notation` format,
+//! turned to its canonical form so symmetric positions share a value,
+//! then the value for the player who just moved: 1 for a sure win,
+//! 0 for a sure loss. Positions missing from the table count as 0.5.
+
+use crate::modules::{
+    board::Board,
+    engine::Engine,
+    game::Game,
+    game_error::{GameError, GameResult},
+    game_status::GameStatus,
+    player::Player,
+    player_symbol::PlayerSymbol,
+    random::Rng,
+    state_file,
+    tournament::PairResult,
+};
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Value of a position the table knows nothing about
+const UNKNOWN_VALUE: f64 = 0.5;
+
+/// What a learning engine knows: how good each position is
+/// for the player who just moved
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ValueTable {
+    /// Keyed by the canonical `Board::to_notation`
+    values: BTreeMap<String, f64>,
+}
+
+impl ValueTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Reads a table written by `save`
+    pub fn load(path: impl AsRef<Path>) -> GameResult<Self> {
+        state_file::load(path)
+    }
+    /// Reads a table written by `save`, or starts an empty one
+    /// if the file doesn't exist yet
+    pub fn load_or_new(path: impl AsRef<Path>) -> GameResult<Self> {
+        state_file::load_or_default(path)
+    }
+    /// Writes the table to `path`, replacing anything already there
+    pub fn save(&self, path: impl AsRef<Path>) -> GameResult<()> {
+        state_file::save(self, path)
+    }
+    /// Gets the number of positions with a value
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+    /// Checks whether nothing has been learned yet
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+    /// Gets the value of `board`, with `to_move` to play next,
+    /// for the player who just moved. Finished games are worth
+    /// what they ended in, whatever the table says.
+    pub fn value(&self, board: &Board, to_move: PlayerSymbol) -> f64 {
+        match board.status() {
+            // Only the player who just moved can have won
+            GameStatus::Won { .. } => 1.0,
+            GameStatus::Draw => 0.5,
+            GameStatus::InProgress => self
+                .values
+                .get(&table_key(board, to_move))
+                .copied()
+                .unwrap_or(UNKNOWN_VALUE),
+        }
+    }
+    /// Moves the value of `board` a `rate` of the way towards `target`.
+    /// Finished games keep the value they ended in.
+    fn update(&mut self, board: &Board, to_move: PlayerSymbol, target: f64, rate: f64) {
+        if board.is_game_over() {
+            return;
+        }
+        let value = self.value(board, to_move);
+        self.values
+            .insert(table_key(board, to_move), value + rate * (target - value));
+    }
+}
+
+impl Display for ValueTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (notation, value) in &self.values {
+            writeln!(f, "{} {}", notation, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ValueTable {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut table = Self::new();
+        for (line_index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_line = || {
+                let (notation, value) = line
+                    .rsplit_once(' ')
+                    .ok_or_else(|| GameError::Parse("Expected a position and a value".into()))?;
+                state_file::canonical_position(notation)?;
+                match value.parse::<f64>() {
+                    // Also rules out NaN, which no move could ever beat
+                    Ok(number) if (0.0..=1.0).contains(&number) => {
+                        Ok((notation.to_string(), number))
+                    }
+                    _ => Err(GameError::Parse(format!(
+                        "Values must be numbers from 0 to 1, not '{}'",
+                        value
+                    ))),
+                }
+            };
+            let (notation, value) =
+                parse_line().map_err(|e: GameError| e.at_line(line_index + 1))?;
+            table.values.insert(notation, value);
+        }
+        Ok(table)
+    }
+}
+
+/// Gets the key a position is stored under, shared by its symmetric positions
+fn table_key(board: &Board, to_move: PlayerSymbol) -> String {
+    board.canonical().0.to_notation(to_move)
+}
+
+/// Opponent that plays the move leading to the position it values most,
+/// learning from every game it finishes.
+/// It starts out knowing nothing, so it needs training before it plays well.
+pub struct LearningEngine {
+    table: ValueTable,
+    /// How far each value moves towards its target after a game
+    learning_rate: f64,
+    /// Chance of a random move while training, to find out about new positions
+    exploration: f64,
+    rng: Rng,
+    /// File the table is saved to after each game
+    save_file: Option<PathBuf>,
+    /// The side played in the game under way
+    side: Option<PlayerSymbol>,
+}
+
+impl LearningEngine {
+    /// Creates an engine that knows what is in `table`,
+    /// learning at a rate of 0.2 and exploring 10% of the time in training
+    pub fn new(table: ValueTable, rng: Rng) -> Self {
+        Self {
+            table,
+            learning_rate: 0.2,
+            exploration: 0.1,
+            rng,
+            save_file: None,
+            side: None,
+        }
+    }
+    /// Sets how far each value moves towards its target after a game,
+    /// clamped to `0.0..=1.0`
+    pub fn with_learning_rate(mut self, learning_rate: f64) -> Self {
+        self.learning_rate = learning_rate.clamp(0.0, 1.0);
+        self
+    }
+    /// Sets the chance of a random move while training, clamped to `0.0..=1.0`
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration.clamp(0.0, 1.0);
+        self
+    }
+    /// Saves the table to `path` after every game played through `Game::play_turn`
+    pub fn with_save_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.save_file = Some(path.into());
+        self
+    }
+    pub fn table(&self) -> &ValueTable {
+        &self.table
+    }
+    /// Plays `games` games against itself on an empty board the size
+    /// of `board`, learning from both sides, and returns X's results
+    pub fn train_self_play(&mut self, board: &Board, games: u32) -> GameResult<PairResult> {
+        let mut results = PairResult::default();
+        for _ in 0..games {
+            let mut game = Game::with_board(board.empty_like());
+            while !game.game_over() {
+                let (x, y) = self.pick_move(&game, true).ok_or(GameError::NoMovesLeft)?;
+                game.apply_move(x, y)?;
+            }
+            self.learn(&game, PlayerSymbol::Cross);
+            self.learn(&game, PlayerSymbol::Nought);
+            count_result(&mut results, &game, PlayerSymbol::Cross);
+        }
+        Ok(results)
+    }
+    /// Plays `games` games against `opponent` on an empty board the size
+    /// of `board`, taking turns to be X, and returns its results
+    pub fn train_against(
+        &mut self,
+        board: &Board,
+        opponent: &mut dyn Engine,
+        games: u32,
+    ) -> GameResult<PairResult> {
+        let mut results = PairResult::default();
+        for number in 0..games {
+            let side = if number % 2 == 0 {
+                PlayerSymbol::Cross
+            } else {
+                PlayerSymbol::Nought
+            };
+            let mut game = Game::with_board(board.empty_like());
+            while !game.game_over() {
+                let choice = if game.current_player_symbol() == side {
+                    self.pick_move(&game, true)
+                } else {
+                    opponent.choose_move(&game)
+                };
+                let (x, y) = choice.ok_or(GameError::NoMovesLeft)?;
+                game.apply_move(x, y)?;
+            }
+            opponent.game_finished(&game)?;
+            self.learn(&game, side);
+            count_result(&mut results, &game, side);
+        }
+        Ok(results)
+    }
+    /// Learns from a finished game as the player of `symbol`,
+    /// going backwards so the result reaches the early moves sooner
+    pub fn learn(&mut self, game: &Game, symbol: PlayerSymbol) {
+        if !game.game_over() {
+            return;
+        }
+        let mut position = game.starting_board();
+        // The positions after each of `symbol`'s moves, with the position
+        // on its next turn if the game went on that long
+        let mut reached: Vec<(Board, Option<Board>)> = Vec::new();
+        let mut player = None;
+        for record in game.history() {
+            let (x, y) = record.coords();
+            position
+                .set(x, y, record.player())
+                .expect("recorded moves are on empty cells");
+            if record.player().symbol() == symbol {
+                player = Some(record.player());
+                reached.push((position.clone(), None));
+            } else if let Some((_, next_turn)) = reached.last_mut() {
+                if !position.is_game_over() {
+                    *next_turn = Some(position.clone());
+                }
+            }
+        }
+        let player = match player {
+            Some(player) => player,
+            None => return,
+        };
+        let result = match game.winner() {
+            Some(winner) if winner.symbol() == symbol => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        for (after_move, next_turn) in reached.iter().rev() {
+            let target = match next_turn {
+                Some(next_turn) => self.best_value(next_turn, player),
+                None => result,
+            };
+            self.table
+                .update(after_move, symbol.opposite(), target, self.learning_rate);
+        }
+    }
+
+    /// Picks the move for the current player of `game` leading to the
+    /// position with the best value, choosing at random between equals,
+    /// or sometimes any move at all if `explore` is set
+    fn pick_move(&mut self, game: &Game, explore: bool) -> Option<(u8, u8)> {
+        let board = game.board();
+        let moves = board.empty_cells();
+        if explore && self.rng.chance(self.exploration) {
+            return self.rng.choose(&moves).copied();
+        }
+        let player = game.current_player();
+        let mut best_value = f64::MIN;
+        let mut best_moves = Vec::new();
+        for (x, y) in moves {
+            let value = self.move_value(board, x, y, player);
+            if value > best_value {
+                best_value = value;
+                best_moves.clear();
+            }
+            if value == best_value {
+                best_moves.push((x, y));
+            }
+        }
+        self.rng.choose(&best_moves).copied()
+    }
+    /// Gets the value of `player` playing at (x, y) on `board`
+    fn move_value(&self, board: &Board, x: u8, y: u8, player: Player) -> f64 {
+        let mut child = board.clone();
+        child.set(x, y, player).expect("only empty cells are tried");
+        self.table.value(&child, player.symbol().opposite())
+    }
+    /// Gets the best value `player` can reach with one move on `board`
+    fn best_value(&self, board: &Board, player: Player) -> f64 {
+        board
+            .empty_cells()
+            .into_iter()
+            .map(|(x, y)| self.move_value(board, x, y, player))
+            .fold(f64::MIN, f64::max)
+    }
+}
+
+impl Engine for LearningEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        self.side = Some(game.current_player_symbol());
+        self.pick_move(game, false)
+    }
+    fn game_finished(&mut self, game: &Game) -> GameResult<()> {
+        if let Some(side) = self.side.take() {
+            self.learn(game, side);
+            if let Some(path) = &self.save_file {
+                self.table.save(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds how `game` went for `symbol` to `results`
+fn count_result(results: &mut PairResult, game: &Game, symbol: PlayerSymbol) {
+    match game.winner() {
+        Some(winner) if winner.symbol() == symbol => results.wins += 1,
+        Some(_) => results.losses += 1,
+        None => results.draws += 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_trained_table() {
+        let mut engine = LearningEngine::new(ValueTable::new(), Rng::new(1));
+        engine.train_self_play(&Board::new(), 200).unwrap();
+        let text = engine.table().to_string();
+        assert_eq!(text.parse::<ValueTable>().unwrap(), *engine.table());
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_from_0_to_1() {
+        for value in ["NaN", "inf", "-0.1", "1.5", "half"] {
+            let line = format!(".../.X./... o {}", value);
+            assert!(line.parse::<ValueTable>().is_err(), "{}", value);
+        }
+        assert!(".../.X./... o 1".parse::<ValueTable>().is_ok());
+    }
+
+    #[test]
+    fn rejects_positions_that_could_never_be_looked_up() {
+        // Not canonical: written .../.../..X o
+        assert!("..X/.../... o 0.5".parse::<ValueTable>().is_err());
+        // Finished
+        assert!("XXX/OO./... o 0.5".parse::<ValueTable>().is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::modules::{
+    engine::Engine,
+    game::Game,
+    game_state::{BitPosition, GameState, Position},
+    player_symbol::PlayerSymbol,
+    random::Rng,
+};
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Playouts per move unless told otherwise
+const DEFAULT_ITERATIONS: u32 = 10_000;
+
+/// Opponent that needs no knowledge of the game beyond its rules:
+/// Monte Carlo tree search with the UCT formula. Each iteration walks down
+/// the tree of moves tried so far, adds one new move, plays the game out
+/// at random and counts the result for every move on the way down.
+/// The move tried most often is played.
+///
+/// With more than one thread each thread grows its own tree
+/// and the visits to each move are added up at the end.
+pub struct MctsEngine {
+    iterations: u32,
+    exploration: f64,
+    threads: usize,
+    /// How long to think about each move, or None to make every iteration
+    time_limit: Option<Duration>,
+    rng: Rng,
+}
+
+/// A move in the search tree and how playouts through it went
+struct Node {
+    /// The move leading here, None at the root
+    mv: Option<(u8, u8)>,
+    /// The player who made `mv`
+    mover: Option<PlayerSymbol>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Legal moves here that don't have a child yet
+    untried: Vec<(u8, u8)>,
+    visits: u32,
+    /// Points `mover` scored in playouts through here, a whole one per win
+    /// and half per draw
+    score: f64,
+}
+
+impl MctsEngine {
+    /// Creates an engine with 10,000 iterations per move
+    /// and the usual exploration constant of √2
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            iterations: DEFAULT_ITERATIONS,
+            exploration: std::f64::consts::SQRT_2,
+            threads: 1,
+            time_limit: None,
+            rng,
+        }
+    }
+    /// Sets how many playouts to make per move, at least one.
+    /// More is stronger but slower.
+    pub fn with_iterations(mut self, iterations: u32) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+    /// Sets how much to favour moves that have been tried less often
+    /// over moves that have done well so far
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+    /// Limits the time spent on each move. The search stops when either
+    /// the time or the iterations run out, but always makes at least one.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+    /// Sets how many threads search at once, at least one.
+    /// The iterations are shared out between them.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+    pub fn exploration(&self) -> f64 {
+        self.exploration
+    }
+    pub fn threads(&self) -> usize {
+        self.threads
+    }
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+    /// Searches `state` and returns the best move for the player to move,
+    /// or None if the game is over
+    pub fn best_move<S: GameState + Sync>(&mut self, state: &S) -> Option<(u8, u8)> {
+        let root_moves = state.legal_moves();
+        if root_moves.len() <= 1 {
+            return root_moves.first().copied();
+        }
+        let deadline = self
+            .time_limit
+            .map(|time_limit| Instant::now() + time_limit);
+        let results = if self.threads == 1 {
+            let mut rng = self.rng.clone();
+            let visits = self.grow_tree(state, root_moves, self.iterations, deadline, &mut rng);
+            self.rng = rng;
+            vec![visits]
+        } else {
+            // Split the iterations as evenly as possible
+            let threads = self.threads as u32;
+            let shares = (0..threads)
+                .map(|index| {
+                    let iterations =
+                        self.iterations / threads + u32::from(index < self.iterations % threads);
+                    (iterations, Rng::new(self.rng.next_u64()))
+                })
+                .collect::<Vec<_>>();
+            let engine = &*self;
+            thread::scope(|scope| {
+                let handles = shares
+                    .into_iter()
+                    .map(|(iterations, mut rng)| {
+                        let root_moves = root_moves.clone();
+                        scope.spawn(move || {
+                            engine.grow_tree(state, root_moves, iterations, deadline, &mut rng)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("search threads don't panic"))
+                    .collect::<Vec<_>>()
+            })
+        };
+        // Add up each move's visits over every tree
+        let mut visits: Vec<((u8, u8), u32)> = Vec::new();
+        for (mv, count) in results.into_iter().flatten() {
+            match visits.iter_mut().find(|(other, _)| *other == mv) {
+                Some((_, total)) => *total += count,
+                None => visits.push((mv, count)),
+            }
+        }
+        visits
+            .into_iter()
+            .max_by_key(|&(_, count)| count)
+            .map(|(mv, _)| mv)
+    }
+
+    /// Builds a search tree for `state` with up to `iterations` playouts,
+    /// stopping early at `deadline`, and returns how often
+    /// each move from `state` was tried
+    fn grow_tree<S: GameState>(
+        &self,
+        state: &S,
+        root_moves: Vec<(u8, u8)>,
+        iterations: u32,
+        deadline: Option<Instant>,
+        rng: &mut Rng,
+    ) -> Vec<((u8, u8), u32)> {
+        let mut tree = vec![Node {
+            mv: None,
+            mover: None,
+            parent: None,
+            children: Vec::new(),
+            untried: root_moves,
+            visits: 0,
+            score: 0.0,
+        }];
+        for iteration in 0..iterations {
+            if iteration > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            let mut state = state.clone();
+            let mut node = 0;
+            // Go down through fully expanded nodes
+            while tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+                node = self.select_child(&tree, node);
+                let (x, y) = tree[node].mv.expect("only the root has no move");
+                state.apply(x, y).expect("the tree only holds legal moves");
+            }
+            // Try one new move
+            if !tree[node].untried.is_empty() {
+                let index = rng.below(tree[node].untried.len());
+                let (x, y) = tree[node].untried.swap_remove(index);
+                let mover = state.to_move();
+                state.apply(x, y).expect("untried moves are legal moves");
+                tree.push(Node {
+                    mv: Some((x, y)),
+                    mover: Some(mover),
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: state.legal_moves(),
+                    visits: 0,
+                    score: 0.0,
+                });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+            // Play the rest of the game at random
+            state.play_out(rng);
+            let winner = state.winner();
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let node = &mut tree[index];
+                node.visits += 1;
+                node.score += match (winner, node.mover) {
+                    (None, _) => 0.5,
+                    (Some(winner), Some(mover)) if winner == mover => 1.0,
+                    _ => 0.0,
+                };
+                current = node.parent;
+            }
+        }
+        tree[0]
+            .children
+            .iter()
+            .filter_map(|&child| Some((tree[child].mv?, tree[child].visits)))
+            .collect()
+    }
+
+    /// Picks the child of `parent` with the highest upper confidence bound
+    fn select_child(&self, tree: &[Node], parent: usize) -> usize {
+        let log_visits = (tree[parent].visits as f64).ln();
+        let bound = |child: usize| {
+            let node = &tree[child];
+            let visits = node.visits as f64;
+            node.score / visits + self.exploration * (log_visits / visits).sqrt()
+        };
+        *tree[parent]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| bound(a).total_cmp(&bound(b)))
+            .expect("only called on nodes with children")
+    }
+}
+
+impl Engine for MctsEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        match BitPosition::from_game(game) {
+            Ok(position) => self.best_move(&position),
+            // Too big for a bitboard
+            Err(_) => self.best_move(&Position::from_game(game)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::game_state::BitPosition;
+
+    #[test]
+    fn takes_a_win_and_blocks_a_loss() {
+        for seed in 0..5 {
+            // X can win at 3,1
+            let game = Game::from_notation("XX./OO./... x").unwrap();
+            let mut engine = MctsEngine::new(Rng::new(seed)).with_iterations(2_000);
+            assert_eq!(engine.choose_move(&game), Some((2, 0)), "seed {}", seed);
+            // O must block at 3,1
+            let game = Game::from_notation("XX./O../... o").unwrap();
+            let mut engine = MctsEngine::new(Rng::new(seed)).with_iterations(2_000);
+            assert_eq!(engine.choose_move(&game), Some((2, 0)), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_moves() {
+        for threads in [1, 2] {
+            let play = || {
+                let mut engine = MctsEngine::new(Rng::new(11))
+                    .with_iterations(300)
+                    .with_threads(threads);
+                let mut game = Game::new();
+                while !game.game_over() {
+                    let (x, y) = engine.choose_move(&game).unwrap();
+                    game.apply_move(x, y).unwrap();
+                }
+                game.history().to_vec()
+            };
+            assert_eq!(play(), play(), "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn searches_a_board_the_same_way_as_a_bitboard() {
+        let game = Game::from_notation("X../.O./... x").unwrap();
+        let on_board = MctsEngine::new(Rng::new(3))
+            .with_iterations(500)
+            .best_move(&Position::from_game(&game));
+        let on_bitboard = MctsEngine::new(Rng::new(3))
+            .with_iterations(500)
+            .best_move(&BitPosition::from_game(&game).unwrap());
+        assert_eq!(on_board, on_bitboard);
+    }
+}
@@ -0,0 +1,348 @@
+//! Donald Michie's MENACE (Matchbox Educable Noughts And Crosses Engine),
+//! which learned noughts and crosses in 1961 with matchboxes and beads.
+//! There is one matchbox for each position it has had to move from, with
+//! symmetric positions sharing a box, and one colour of bead for each
+//! move that isn't symmetric to another. It plays by drawing a bead at
+//! random from the box for the position. After the game every bead it
+//! drew goes back with three more if it won or one more for a draw,
+//! and stays out if it lost, so moves that did well get likelier.
+//!
+//! A new box starts with four beads of each colour for its first move,
+//! three for its second, two for its third and one after that. A box that
+//! runs out of beads is filled up again the next time it is needed,
+//! where Michie's MENACE would have resigned. Loading checks that every
+//! bead is for an empty cell of its box's position.
+//!
+//! The boxes are saved one per line, sorted by position:
+//!
+//! ```text
+//! .../.X./... o: 1,1=3 2,1=1
+//! ```
+//!
+//! That is the canonical position in `Board::to_notation` format, then
+//! each move on that board as 1-based `x,y` with the beads left for it.
+
+use crate::modules::{
+    board::Board,
+    engine::Engine,
+    game::Game,
+    game_error::{GameError, GameResult},
+    move_record::parse_coords,
+    player_symbol::PlayerSymbol,
+    random::Rng,
+    state_file,
+};
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Beads of each colour in a new box, by how many moves MENACE has already made
+const STARTING_BEADS: [u32; 4] = [4, 3, 2, 1];
+
+/// Beads added for each one drawn in a game MENACE won
+const WIN_BEADS: u32 = 3;
+
+/// Beads added for each one drawn in a drawn game
+const DRAW_BEADS: u32 = 1;
+
+/// The beads for one position: how many there are for each move
+#[derive(Clone, PartialEq, Debug)]
+pub struct Matchbox {
+    beads: Vec<((u8, u8), u32)>,
+}
+
+impl Matchbox {
+    /// Fills a new box for the canonical position `board`, with one
+    /// colour for each move that isn't a rotation or reflection of another
+    fn new(board: &Board) -> Self {
+        let (width, height) = (board.width(), board.height());
+        let moves_made = width as usize * height as usize - board.empty_cells().len();
+        let beads = STARTING_BEADS[(moves_made / 2).min(STARTING_BEADS.len() - 1)];
+        // The symmetries that leave the position as it is
+        let same_position = board
+            .symmetries()
+            .into_iter()
+            .filter(|&symmetry| board.transformed(symmetry) == *board)
+            .collect::<Vec<_>>();
+        // Keep the first of each set of symmetric moves, top row first
+        let beads = board
+            .empty_cells()
+            .into_iter()
+            .filter(|&(x, y)| {
+                same_position.iter().all(|symmetry| {
+                    let (other_x, other_y) = symmetry.apply(x, y, width, height);
+                    (other_y, other_x) >= (y, x)
+                })
+            })
+            .map(|mv| (mv, beads))
+            .collect();
+        Self { beads }
+    }
+    /// Gets each move on the canonical board with the number of beads for it
+    pub fn beads(&self) -> &[((u8, u8), u32)] {
+        &self.beads
+    }
+    /// Gets the number of beads in the box
+    pub fn total(&self) -> u32 {
+        self.beads.iter().map(|&(_, count)| count).sum()
+    }
+    /// Picks a move at random, each bead as likely as any other,
+    /// or None if the box is empty
+    fn draw(&self, rng: &mut Rng) -> Option<(u8, u8)> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let mut bead = rng.below(total as usize) as u32;
+        for &(mv, count) in &self.beads {
+            if bead < count {
+                return Some(mv);
+            }
+            bead -= count;
+        }
+        None
+    }
+    /// Puts back the bead for `mv` along with `added` more,
+    /// or keeps it out if `added` is None
+    fn reinforce(&mut self, mv: (u8, u8), added: Option<u32>) {
+        if let Some((_, count)) = self.beads.iter_mut().find(|(other, _)| *other == mv) {
+            *count = match added {
+                Some(added) => *count + added,
+                None => count.saturating_sub(1),
+            };
+        }
+    }
+}
+
+/// Every matchbox MENACE has, one per canonical position
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Matchboxes {
+    /// Keyed by the canonical `Board::to_notation`
+    boxes: BTreeMap<String, Matchbox>,
+}
+
+impl Matchboxes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Reads boxes written by `save`
+    pub fn load(path: impl AsRef<Path>) -> GameResult<Self> {
+        state_file::load(path)
+    }
+    /// Reads boxes written by `save`, or starts with none
+    /// if the file doesn't exist yet
+    pub fn load_or_new(path: impl AsRef<Path>) -> GameResult<Self> {
+        state_file::load_or_default(path)
+    }
+    /// Writes the boxes to `path`, replacing anything already there
+    pub fn save(&self, path: impl AsRef<Path>) -> GameResult<()> {
+        state_file::save(self, path)
+    }
+    /// Gets the number of boxes
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+    /// Checks whether there are no boxes yet
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+    /// Gets the box for `board` with `to_move` to play, or any rotation
+    /// or reflection of it. Its moves are on `board.canonical()`.
+    pub fn get(&self, board: &Board, to_move: PlayerSymbol) -> Option<&Matchbox> {
+        self.boxes.get(&board.canonical().0.to_notation(to_move))
+    }
+}
+
+impl Display for Matchboxes {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (notation, matchbox) in &self.boxes {
+            write!(f, "{}:", notation)?;
+            for &((x, y), count) in &matchbox.beads {
+                write!(f, " {},{}={}", x + 1, y + 1, count)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Matchboxes {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut boxes = Self::new();
+        for (line_index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let parse_line = || {
+                let (notation, beads) = line.split_once(':').ok_or_else(|| {
+                    GameError::Parse("Expected a position, a colon and the beads".into())
+                })?;
+                let (board, _) = state_file::canonical_position(notation)?;
+                let beads = beads
+                    .split_whitespace()
+                    .map(|bead| {
+                        let (coords, count) = bead.split_once('=').ok_or_else(|| {
+                            GameError::Parse(format!("Expected beads like 1,2=3, not '{}'", bead))
+                        })?;
+                        let count = count.parse::<u32>().map_err(|_| {
+                            GameError::Parse(format!("'{}' is not a number", count))
+                        })?;
+                        let (x, y) = parse_coords(coords)?;
+                        if !is_empty_cell(&board, x, y) {
+                            return Err(GameError::Parse(format!(
+                                "{} isn't an empty cell of the position",
+                                coords
+                            )));
+                        }
+                        Ok(((x, y), count))
+                    })
+                    .collect::<GameResult<Vec<_>>>()?;
+                Ok((notation.trim().to_string(), Matchbox { beads }))
+            };
+            let (notation, matchbox) =
+                parse_line().map_err(|e: GameError| e.at_line(line_index + 1))?;
+            boxes.boxes.insert(notation, matchbox);
+        }
+        Ok(boxes)
+    }
+}
+
+/// Opponent that learns the way Michie's matchboxes did.
+/// It starts out playing at random and gets better with every game
+/// it finishes, so it is meant for the 3x3 board it was built for.
+pub struct MenaceEngine {
+    boxes: Matchboxes,
+    rng: Rng,
+    /// File the boxes are saved to after each game
+    save_file: Option<PathBuf>,
+    /// The side played in the game under way
+    side: Option<PlayerSymbol>,
+    /// The box and bead for each move drawn this game,
+    /// with how many moves had been played before it
+    drawn: Vec<(String, (u8, u8), usize)>,
+}
+
+impl MenaceEngine {
+    /// Creates an engine that plays from `boxes`
+    pub fn new(boxes: Matchboxes, rng: Rng) -> Self {
+        Self {
+            boxes,
+            rng,
+            save_file: None,
+            side: None,
+            drawn: Vec::new(),
+        }
+    }
+    /// Saves the boxes to `path` after every game played through `Game::play_turn`
+    pub fn with_save_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.save_file = Some(path.into());
+        self
+    }
+    pub fn boxes(&self) -> &Matchboxes {
+        &self.boxes
+    }
+}
+
+impl Engine for MenaceEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        let moves_played = game.history().len();
+        // Forget moves that were taken back, or were in an earlier game
+        self.drawn.retain(|&(_, _, before)| before < moves_played);
+        let to_move = game.current_player_symbol();
+        self.side = Some(to_move);
+        let (board, symmetry) = game.board().canonical();
+        let key = board.to_notation(to_move);
+        let matchbox = self
+            .boxes
+            .boxes
+            .entry(key.clone())
+            .or_insert_with(|| Matchbox::new(&board));
+        let playable = matchbox
+            .beads
+            .iter()
+            .all(|&((x, y), _)| is_empty_cell(&board, x, y));
+        if matchbox.total() == 0 || !playable {
+            *matchbox = Matchbox::new(&board);
+        }
+        let (x, y) = matchbox.draw(&mut self.rng)?;
+        self.drawn.push((key, (x, y), moves_played));
+        Some(
+            symmetry
+                .inverse()
+                .apply(x, y, board.width(), board.height()),
+        )
+    }
+    fn game_finished(&mut self, game: &Game) -> GameResult<()> {
+        let side = match self.side.take() {
+            Some(side) => side,
+            None => return Ok(()),
+        };
+        let added = match game.winner() {
+            Some(winner) if winner.symbol() == side => Some(WIN_BEADS),
+            Some(_) => None,
+            None => Some(DRAW_BEADS),
+        };
+        for (key, mv, _) in self.drawn.drain(..) {
+            if let Some(matchbox) = self.boxes.boxes.get_mut(&key) {
+                matchbox.reinforce(mv, added);
+            }
+        }
+        if let Some(path) = &self.save_file {
+            self.boxes.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks whether (x, y) is on `board` and nobody has played there
+fn is_empty_cell(board: &Board, x: u8, y: u8) -> bool {
+    x < board.width() && y < board.height() && board.get(x, y).is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_with_one_colour_per_distinct_move() {
+        let matchbox = Matchbox::new(&Board::new());
+        // Corner, edge and centre
+        assert_eq!(matchbox.beads(), [((0, 0), 4), ((1, 0), 4), ((1, 1), 4)]);
+    }
+
+    #[test]
+    fn round_trips_boxes_after_some_games() {
+        let mut menace = MenaceEngine::new(Matchboxes::new(), Rng::new(3));
+        for _ in 0..50 {
+            let mut game = Game::new();
+            while !game.game_over() {
+                let (x, y) = menace.choose_move(&game).unwrap();
+                game.apply_move(x, y).unwrap();
+            }
+            menace.game_finished(&game).unwrap();
+        }
+        let text = menace.boxes().to_string();
+        assert_eq!(text.parse::<Matchboxes>().unwrap(), *menace.boxes());
+    }
+
+    #[test]
+    fn rejects_beads_that_could_not_be_played() {
+        // The centre is taken
+        assert!(".../.X./... o: 2,2=5".parse::<Matchboxes>().is_err());
+        // Off the board
+        assert!(".../.X./... o: 4,1=5".parse::<Matchboxes>().is_err());
+        assert!(".../.X./... o: 1,1=5 2,1=2".parse::<Matchboxes>().is_ok());
+    }
+
+    #[test]
+    fn rejects_boxes_that_could_never_be_opened() {
+        assert!("X../.../... o: 2,2=1".parse::<Matchboxes>().is_err());
+    }
+}
@@ -0,0 +1,691 @@
+use crate::modules::{
+    bitboard::BitBoard,
+    board::Board,
+    engine::Engine,
+    game::Game,
+    game_status::GameStatus,
+    player::Player,
+    player_symbol::PlayerSymbol,
+    transposition_table::{Bound, TableEntry, TranspositionTable},
+};
+
+use std::{
+    cmp::Reverse,
+    sync::atomic::{AtomicI32, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Score for a win found straight away.
+/// Each extra ply before the win knocks one off,
+/// so faster wins and slower losses are preferred
+const WIN_SCORE: i32 = 100;
+
+/// Positions the transposition table holds unless told otherwise
+const DEFAULT_TABLE_SIZE: usize = 1 << 16;
+
+/// How many positions to search between looks at the clock
+const NODES_PER_CLOCK_CHECK: u64 = 1024;
+
+/// Perfect-play opponent: searches the whole game tree
+/// with minimax and alpha-beta pruning.
+///
+/// Given a time limit it searches one move ahead, then two, and so on,
+/// playing the best move of the deepest search that finished in time.
+///
+/// With more than one thread the moves from the current position are
+/// dealt out between the threads, which search them side by side
+/// and share the best score found so far to cut each other's searches short.
+///
+/// The search runs on a `BitBoard` whenever the board fits in one.
+pub struct MinimaxEngine {
+    /// How many moves ahead to look, or None to search to the end of the game
+    max_depth: Option<i32>,
+    /// How long to think about each move, or None to take as long as needed
+    time_limit: Option<Duration>,
+    /// Positions each thread's transposition table holds
+    table_size: usize,
+    /// One per thread
+    searchers: Vec<Searcher>,
+}
+
+impl Default for MinimaxEngine {
+    fn default() -> Self {
+        Self {
+            max_depth: None,
+            time_limit: None,
+            table_size: DEFAULT_TABLE_SIZE,
+            searchers: vec![Searcher::new(DEFAULT_TABLE_SIZE)],
+        }
+    }
+}
+
+impl MinimaxEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a weaker engine that stops searching after `max_depth` moves
+    /// and treats any position it has not solved by then as a draw
+    pub fn with_max_depth(max_depth: i32) -> Self {
+        Self {
+            max_depth: Some(max_depth),
+            ..Self::default()
+        }
+    }
+    /// Sets how many positions each thread's transposition table holds.
+    /// Bigger boards search faster with a bigger table.
+    pub fn with_table_size(mut self, entries: usize) -> Self {
+        self.table_size = entries;
+        let threads = self.threads();
+        self.searchers = (0..threads).map(|_| Searcher::new(entries)).collect();
+        self
+    }
+    /// Limits the time spent on each move, searching deeper and deeper
+    /// until it runs out. At least a one-move search is always finished.
+    pub fn with_time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+    /// Sets how many threads search at once, at least one.
+    /// Each thread has its own transposition table.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        let table_size = self.table_size;
+        self.searchers = (0..threads.max(1))
+            .map(|_| Searcher::new(table_size))
+            .collect();
+        self
+    }
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.time_limit
+    }
+    pub fn threads(&self) -> usize {
+        self.searchers.len()
+    }
+
+    /// Scores every move for `player` on `board`, starting with `first`,
+    /// and returns the best one with its score. Ties go to the move
+    /// searched first, however the moves were split between threads.
+    fn search_root<B: SearchBoard>(
+        &mut self,
+        board: &B,
+        player: Player,
+        opponent: Player,
+        first: Option<(u8, u8)>,
+        depth_limit: Option<i32>,
+        deadline: Option<Instant>,
+    ) -> Option<((u8, u8), i32)> {
+        let moves = ordered_moves(board, first);
+        let shared_best = AtomicI32::new(-WIN_SCORE - 1);
+        for searcher in &mut self.searchers {
+            searcher.start(depth_limit, deadline);
+        }
+        let results = if let [searcher] = self.searchers.as_mut_slice() {
+            vec![searcher.search_moves(&mut board.clone(), player, opponent, &moves, &shared_best)]
+        } else {
+            let threads = self.searchers.len();
+            thread::scope(|scope| {
+                let handles = self
+                    .searchers
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(index, searcher)| {
+                        // Deal the moves out in turn, so the one most likely
+                        // to be best is searched straight away
+                        let share = moves
+                            .iter()
+                            .copied()
+                            .skip(index)
+                            .step_by(threads)
+                            .collect::<Vec<_>>();
+                        let mut board = board.clone();
+                        let shared_best = &shared_best;
+                        scope.spawn(move || {
+                            searcher.search_moves(&mut board, player, opponent, &share, shared_best)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                handles
+                    .into_iter()
+                    .map(|handle| handle.join().expect("search threads don't panic"))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let order = |mv: (u8, u8)| moves.iter().position(|&m| m == mv);
+        results
+            .into_iter()
+            .flatten()
+            .max_by_key(|&(mv, score)| (score, Reverse(order(mv))))
+    }
+    /// Checks whether any thread ran out of time in the last search
+    fn timed_out(&self) -> bool {
+        self.searchers.iter().any(|searcher| searcher.timed_out)
+    }
+    /// Searches one move deeper each time until the time runs out,
+    /// the game has been searched to the end, or a forced result is found
+    fn iterative_deepening<B: SearchBoard>(
+        &mut self,
+        board: &B,
+        player: Player,
+        opponent: Player,
+        time_limit: Duration,
+    ) -> Option<(u8, u8)> {
+        let deadline = Instant::now() + time_limit;
+        let moves_left = board.empty_cells().len() as i32;
+        let deepest = self
+            .max_depth
+            .map_or(moves_left, |max_depth| max_depth.min(moves_left));
+        let mut best: Option<((u8, u8), i32)> = None;
+        for depth in 1..=deepest {
+            let first = best.map(|(mv, _)| mv);
+            let result =
+                self.search_root(board, player, opponent, first, Some(depth), Some(deadline));
+            if self.timed_out() {
+                // A search cut short can't be trusted, unless there is nothing else
+                best = best.or(result);
+                break;
+            }
+            best = result;
+            // Only a win or loss scores anything but 0. One this search
+            // reached itself can't be beaten by a deeper search, but the
+            // table can hand back a slower one found by an earlier, deeper
+            // search, and a faster one may still be out of reach.
+            if best.is_some_and(|(_, score)| score != 0 && WIN_SCORE - score.abs() < depth) {
+                break;
+            }
+        }
+        best.map(|(mv, _)| mv)
+    }
+    /// Finds the best move for `player` on `board`
+    fn best_move<B: SearchBoard>(
+        &mut self,
+        board: &B,
+        player: Player,
+        opponent: Player,
+    ) -> Option<(u8, u8)> {
+        match self.time_limit {
+            Some(time_limit) => self.iterative_deepening(board, player, opponent, time_limit),
+            None => self
+                .search_root(board, player, opponent, None, self.max_depth, None)
+                .map(|(mv, _)| mv),
+        }
+    }
+}
+
+/// What the search needs from a board, so it can run on a `BitBoard`
+/// or, for boards too big for one, on the `Board` itself
+trait SearchBoard: Clone + Send {
+    /// Gets the coordinates of every empty cell, row by row
+    fn empty_cells(&self) -> Vec<(u8, u8)>;
+    /// Gets the hash of the position with `to_move` to play
+    fn position_hash(&self, to_move: PlayerSymbol) -> u64;
+    /// Plays `player` on the empty cell (x, y) of a game still going,
+    /// and says whether that won
+    fn play(&mut self, x: u8, y: u8, player: Player) -> bool;
+    /// Takes back `player`'s move at (x, y)
+    fn take_back(&mut self, x: u8, y: u8, player: Player);
+    /// Checks whether a move that didn't win has drawn the game
+    fn is_drawn(&self) -> bool;
+}
+
+impl SearchBoard for Board {
+    fn empty_cells(&self) -> Vec<(u8, u8)> {
+        Board::empty_cells(self)
+    }
+    fn position_hash(&self, to_move: PlayerSymbol) -> u64 {
+        Board::position_hash(self, to_move)
+    }
+    fn play(&mut self, x: u8, y: u8, player: Player) -> bool {
+        self.set(x, y, player)
+            .expect("search only plays on empty cells");
+        self.get_winner().is_some()
+    }
+    fn take_back(&mut self, x: u8, y: u8, _player: Player) {
+        self.clear(x, y)
+            .expect("search only clears cells it played on");
+    }
+    fn is_drawn(&self) -> bool {
+        *self.status() == GameStatus::Draw
+    }
+}
+
+impl SearchBoard for BitBoard {
+    fn empty_cells(&self) -> Vec<(u8, u8)> {
+        self.moves().map(|cell| self.coords(cell)).collect()
+    }
+    fn position_hash(&self, to_move: PlayerSymbol) -> u64 {
+        BitBoard::position_hash(self, to_move)
+    }
+    fn play(&mut self, x: u8, y: u8, player: Player) -> bool {
+        let cell = self.cell(x, y);
+        self.make_move(cell, player.symbol());
+        self.wins_through(cell, player.symbol())
+    }
+    fn take_back(&mut self, x: u8, y: u8, player: Player) {
+        self.unmake_move(self.cell(x, y), player.symbol());
+    }
+    fn is_drawn(&self) -> bool {
+        self.is_full()
+    }
+}
+
+/// The state of one thread's search
+struct Searcher {
+    /// Positions already searched, kept from one move to the next
+    table: TranspositionTable,
+    /// How far ahead the search under way looks
+    depth_limit: Option<i32>,
+    /// When the search under way has to stop
+    deadline: Option<Instant>,
+    /// Positions searched so far this move
+    nodes: u64,
+    /// Set once the deadline has passed, so the search unwinds
+    /// without trusting or storing any more scores
+    timed_out: bool,
+}
+
+impl Searcher {
+    fn new(table_size: usize) -> Self {
+        Self {
+            table: TranspositionTable::new(table_size),
+            depth_limit: None,
+            deadline: None,
+            nodes: 0,
+            timed_out: false,
+        }
+    }
+    /// Gets ready for a new search
+    fn start(&mut self, depth_limit: Option<i32>, deadline: Option<Instant>) {
+        self.depth_limit = depth_limit;
+        self.deadline = deadline;
+        self.nodes = 0;
+        self.timed_out = false;
+    }
+    /// Scores each of `moves` for `player` in turn and returns the first
+    /// of the best ones with its score, or None if none of them can beat
+    /// `shared_best`, the best score any thread has found so far
+    fn search_moves<B: SearchBoard>(
+        &mut self,
+        board: &mut B,
+        player: Player,
+        opponent: Player,
+        moves: &[(u8, u8)],
+        shared_best: &AtomicI32,
+    ) -> Option<((u8, u8), i32)> {
+        let mut best = None;
+        let mut own_best = -WIN_SCORE - 1;
+        for &(x, y) in moves {
+            // A move that only ties another thread's best still gets
+            // its exact score, so the tie can go to the move searched first
+            let alpha = own_best.max(shared_best.load(Ordering::Relaxed) - 1);
+            let score = self.score_move(board, x, y, player, opponent, 0, alpha, WIN_SCORE + 1);
+            if self.timed_out {
+                break;
+            }
+            // Anything else is only an upper bound on the move's score
+            if score > alpha {
+                own_best = score;
+                best = Some(((x, y), score));
+                shared_best.fetch_max(score, Ordering::Relaxed);
+            }
+        }
+        best
+    }
+    /// Checks the clock every so often, noting when the deadline has passed
+    fn check_time(&mut self) -> bool {
+        self.nodes += 1;
+        if !self.timed_out && self.nodes.is_multiple_of(NODES_PER_CLOCK_CHECK) {
+            self.timed_out = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.timed_out
+    }
+
+    /// Scores `board` from the point of view of `player`, who is to move.
+    /// Written in negamax form: the opponent's best score is our worst.
+    fn search<B: SearchBoard>(
+        &mut self,
+        board: &mut B,
+        player: Player,
+        opponent: Player,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if self.check_time() {
+            return 0;
+        }
+        let key = board.position_hash(player.symbol());
+        let remaining = self.remaining_depth(depth);
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(key) {
+            table_move = entry.best_move;
+            if entry.depth >= remaining {
+                let score = score_from_table(entry.score, depth);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+        let original_alpha = alpha;
+        let mut best = -WIN_SCORE;
+        let mut best_move = None;
+        for (x, y) in ordered_moves(board, table_move) {
+            let score = self.score_move(board, x, y, player, opponent, depth, alpha, beta);
+            if self.timed_out {
+                return 0;
+            }
+            if best_move.is_none() || score > best {
+                best = score;
+                best_move = Some((x, y));
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.store(TableEntry {
+            key,
+            depth: remaining,
+            score: score_to_table(best, depth),
+            bound,
+            best_move,
+        });
+        best
+    }
+
+    /// Scores `player` playing at (x, y) on `board`,
+    /// leaving the board as it was afterwards
+    #[allow(clippy::too_many_arguments)]
+    fn score_move<B: SearchBoard>(
+        &mut self,
+        board: &mut B,
+        x: u8,
+        y: u8,
+        player: Player,
+        opponent: Player,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let score = if board.play(x, y, player) {
+            WIN_SCORE - depth
+        } else if board.is_drawn()
+            || self
+                .depth_limit
+                .is_some_and(|depth_limit| depth + 1 >= depth_limit)
+        {
+            // A draw, or as far as the search looks
+            0
+        } else {
+            -self.search(board, opponent, player, depth + 1, -beta, -alpha)
+        };
+        board.take_back(x, y, player);
+        score
+    }
+
+    /// Gets how many more moves the search looks ahead from `depth`
+    fn remaining_depth(&self, depth: i32) -> i32 {
+        match self.depth_limit {
+            Some(depth_limit) => depth_limit - depth,
+            None => i32::MAX,
+        }
+    }
+}
+
+/// Gets the empty cells of `board`, with `first` moved to the front
+fn ordered_moves(board: &impl SearchBoard, first: Option<(u8, u8)>) -> Vec<(u8, u8)> {
+    let mut moves = board.empty_cells();
+    if let Some(index) = first.and_then(|first| moves.iter().position(|&m| m == first)) {
+        moves[..=index].rotate_right(1);
+    }
+    moves
+}
+
+/// Win and loss scores count plies from the root of the search, but a
+/// position can come up at different depths, so the table counts them
+/// from the position itself
+fn score_to_table(score: i32, depth: i32) -> i32 {
+    match score {
+        s if s > 0 => s + depth,
+        s if s < 0 => s - depth,
+        _ => 0,
+    }
+}
+
+/// Undoes `score_to_table` for a position `depth` plies from the root
+fn score_from_table(score: i32, depth: i32) -> i32 {
+    match score {
+        s if s > 0 => s - depth,
+        s if s < 0 => s + depth,
+        _ => 0,
+    }
+}
+
+impl Engine for MinimaxEngine {
+    fn choose_move(&mut self, game: &Game) -> Option<(u8, u8)> {
+        let board = game.board();
+        let player = game.current_player();
+        let opponent = *game.get_next_player();
+        match BitBoard::from_board(board) {
+            Ok(bitboard) => self.best_move(&bitboard, player, opponent),
+            // Too big for a bitboard
+            Err(_) => self.best_move(board, player, opponent),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::solver::SolutionTable;
+
+    use std::collections::{HashMap, HashSet};
+
+    /// Plays `engine` as `side` from `game` against every possible reply,
+    /// failing if any line ends in a loss, and returns how many games it played
+    fn never_loses(engine: &mut MinimaxEngine, game: &mut Game, side: PlayerSymbol) -> u32 {
+        if game.game_over() {
+            let winner = game.winner().map(|winner| winner.symbol());
+            assert_ne!(
+                winner,
+                Some(side.opposite()),
+                "lost after {:?}",
+                game.history()
+            );
+            return 1;
+        }
+        if game.current_player_symbol() == side {
+            let (x, y) = engine.choose_move(game).expect("the game isn't over");
+            game.apply_move(x, y).unwrap();
+            let games = never_loses(engine, game, side);
+            game.undo().unwrap();
+            return games;
+        }
+        let mut games = 0;
+        for (x, y) in game.board().empty_cells() {
+            game.apply_move(x, y).unwrap();
+            games += never_loses(engine, game, side);
+            game.undo().unwrap();
+        }
+        games
+    }
+
+    #[test]
+    fn never_loses_on_3x3() {
+        for side in [PlayerSymbol::Cross, PlayerSymbol::Nought] {
+            let mut engine = MinimaxEngine::new();
+            assert!(never_loses(&mut engine, &mut Game::new(), side) > 0);
+        }
+    }
+
+    #[test]
+    fn never_loses_on_3x3_with_threads() {
+        let mut engine = MinimaxEngine::new().with_threads(2);
+        never_loses(&mut engine, &mut Game::new(), PlayerSymbol::Nought);
+    }
+
+    /// Asks `engine` for a move in every position reachable from `game`,
+    /// failing if it isn't one of the best moves in `solutions`
+    fn plays_perfectly(
+        engine: &mut MinimaxEngine,
+        game: &mut Game,
+        solutions: &SolutionTable,
+        seen: &mut HashSet<String>,
+    ) {
+        let notation = game.board().to_notation(game.current_player_symbol());
+        if game.game_over() || !seen.insert(notation.clone()) {
+            return;
+        }
+        let best_moves = solutions.best_moves(game.board(), game.current_player());
+        let chosen = engine.choose_move(game).expect("the game isn't over");
+        assert!(
+            best_moves.contains(&chosen),
+            "played {:?} on {}, not one of {:?}",
+            chosen,
+            notation,
+            best_moves
+        );
+        for (x, y) in game.board().empty_cells() {
+            game.apply_move(x, y).unwrap();
+            plays_perfectly(engine, game, solutions, seen);
+            game.undo().unwrap();
+        }
+    }
+
+    #[test]
+    fn finds_the_fastest_win_with_a_table_kept_between_moves() {
+        let solutions = SolutionTable::solve(&Board::new());
+        // Long enough never to run out, so every search goes as deep as it needs
+        let mut engine = MinimaxEngine::new().with_time_limit(Duration::from_secs(60));
+        let mut seen = HashSet::new();
+        plays_perfectly(&mut engine, &mut Game::new(), &solutions, &mut seen);
+        assert!(seen.len() > 4000);
+    }
+
+    /// Scores `board` for `player`, who is to move, with plain minimax:
+    /// no pruning and no transposition table, only remembering positions
+    /// already scored. Scores count plies from `board`, like the search's.
+    fn plain_score(
+        board: &mut Board,
+        player: Player,
+        opponent: Player,
+        scored: &mut HashMap<String, i32>,
+    ) -> i32 {
+        let notation = board.to_notation(player.symbol());
+        if let Some(&score) = scored.get(&notation) {
+            return score;
+        }
+        let mut best = -WIN_SCORE - 1;
+        for (x, y) in board.empty_cells() {
+            board.set(x, y, player).unwrap();
+            let score = if board.get_winner().is_some() {
+                WIN_SCORE
+            } else if board.is_game_over() {
+                0
+            } else {
+                // One ply further from here than from the next position
+                let reply = plain_score(board, opponent, player, scored);
+                -(reply - reply.signum())
+            };
+            board.clear(x, y).unwrap();
+            best = best.max(score);
+        }
+        scored.insert(notation, best);
+        best
+    }
+
+    #[test]
+    fn search_with_the_table_matches_plain_minimax() {
+        // One engine for every position, so the table carries over
+        // from one search to the next the way it does in a game
+        let mut engine = MinimaxEngine::new();
+        let mut deepening = MinimaxEngine::new().with_time_limit(Duration::from_secs(60));
+        let mut scored = HashMap::new();
+        let mut positions = vec![Game::new()];
+        let mut seen = HashSet::new();
+        while let Some(mut game) = positions.pop() {
+            if !seen.insert(game.to_notation()) {
+                continue;
+            }
+            let (player, opponent) = (game.current_player(), *game.get_next_player());
+            let mut board = game.board().clone();
+            let expected = plain_score(&mut board, player, opponent, &mut scored);
+            let bitboard = BitBoard::from_board(&board).unwrap();
+            let (_, score) = engine
+                .search_root(&bitboard, player, opponent, None, None, None)
+                .unwrap();
+            assert_eq!(score, expected, "{}", game.to_notation());
+            let (x, y) = deepening.choose_move(&game).unwrap();
+            board.set(x, y, player).unwrap();
+            let chosen = if board.get_winner().is_some() {
+                WIN_SCORE
+            } else if board.is_game_over() {
+                0
+            } else {
+                let reply = plain_score(&mut board, opponent, player, &mut scored);
+                -(reply - reply.signum())
+            };
+            board.clear(x, y).unwrap();
+            assert_eq!(chosen, expected, "{} with a time limit", game.to_notation());
+            for (x, y) in board.empty_cells() {
+                game.apply_move(x, y).unwrap();
+                if !game.game_over() {
+                    positions.push(Game::from_notation(&game.to_notation()).unwrap());
+                }
+                game.undo().unwrap();
+            }
+        }
+        assert!(seen.len() > 4000);
+    }
+
+    #[test]
+    fn draws_against_itself() {
+        let mut engine = MinimaxEngine::new();
+        let mut game = Game::new();
+        while !game.game_over() {
+            let (x, y) = engine.choose_move(&game).unwrap();
+            game.apply_move(x, y).unwrap();
+        }
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn bitboard_search_matches_board_search() {
+        for notation in [
+            "X../.O./... x",
+            "XO../.X../..O./.... x",
+            "...../.X.../..O../...../..... x 4",
+        ] {
+            let game = Game::from_notation(notation).unwrap();
+            let (player, opponent) = (game.current_player(), *game.get_next_player());
+            let bitboard = BitBoard::from_board(game.board()).unwrap();
+            let depth = Some(4);
+            let on_board =
+                MinimaxEngine::new().search_root(game.board(), player, opponent, None, depth, None);
+            let on_bitboard =
+                MinimaxEngine::new().search_root(&bitboard, player, opponent, None, depth, None);
+            assert_eq!(on_bitboard, on_board, "{}", notation);
+        }
+    }
+
+    #[test]
+    fn takes_a_win_and_blocks_a_loss() {
+        // X can win at 3,1
+        let mut game = Game::from_notation("XX./OO./... x").unwrap();
+        assert_eq!(MinimaxEngine::new().choose_move(&game), Some((2, 0)));
+        // O must block at 3,1
+        game = Game::from_notation("XX./O../... o").unwrap();
+        assert_eq!(MinimaxEngine::new().choose_move(&game), Some((2, 0)));
+    }
+}
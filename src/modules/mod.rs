@@ -1,6 +1,30 @@
-pub(crate) mod board;
-pub(crate) mod game_mode;
-pub(crate) mod player;
-pub(crate) mod player_symbol;
-pub(crate) mod game;
-pub(crate) mod input_error;
\ No newline at end of file
+pub mod board;
+pub mod game_mode;
+pub mod player;
+pub mod player_symbol;
+pub mod game;
+pub mod game_status;
+pub mod input_error;
+pub mod game_error;
+pub mod player_controller;
+pub mod engine;
+pub mod minimax;
+pub mod greedy;
+pub mod random;
+pub mod ai_difficulty;
+pub mod move_record;
+pub mod save_file;
+pub mod game_record;
+pub mod replay;
+pub mod variant;
+pub mod tournament;
+pub mod solver;
+pub mod symmetry;
+pub mod bitboard;
+pub mod transposition_table;
+pub mod game_state;
+pub mod mcts;
+pub mod engine_kind;
+pub mod learning;
+pub mod menace;
+pub mod state_file;
@@ -0,0 +1,70 @@
+use crate::modules::{
+    game_error::{GameError, GameResult},
+    player::Player,
+};
+
+use std::fmt::Display;
+
+/// Parses 1-based "x,y" coordinates, as written in saved games,
+/// into 0-based ones. Whether they are on the board is up to the board.
+pub fn parse_coords(text: &str) -> GameResult<(u8, u8)> {
+    let (x, y) = text.trim().split_once(',').ok_or_else(|| {
+        GameError::Parse(format!(
+            "Expected coordinates like 1,2 but found '{}'",
+            text
+        ))
+    })?;
+    let parse = |value: &str| match value.trim().parse::<u8>() {
+        Ok(value) if value > 0 => Ok(value - 1),
+        _ => Err(GameError::Parse(format!(
+            "'{}' is not a valid coordinate",
+            value.trim()
+        ))),
+    };
+    Ok((parse(x)?, parse(y)?))
+}
+
+/// One move in a game's history
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct MoveRecord {
+    player: Player,
+    x: u8,
+    y: u8,
+    move_number: usize,
+}
+
+impl MoveRecord {
+    pub fn new(player: Player, x: u8, y: u8, move_number: usize) -> Self {
+        Self {
+            player,
+            x,
+            y,
+            move_number,
+        }
+    }
+    /// Gets the player who made the move
+    pub fn player(&self) -> Player {
+        self.player
+    }
+    /// Gets the 0-based (x, y) coordinates of the move
+    pub fn coords(&self) -> (u8, u8) {
+        (self.x, self.y)
+    }
+    /// Gets the 1-based position of the move in the game
+    pub fn move_number(&self) -> usize {
+        self.move_number
+    }
+}
+
+impl Display for MoveRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}. {} {},{}",
+            self.move_number,
+            self.player,
+            self.x + 1,
+            self.y + 1
+        )
+    }
+}
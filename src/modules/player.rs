@@ -1,12 +1,9 @@
 use crate::modules::player_symbol::PlayerSymbol;
 
-use anyhow::{anyhow, bail, Result as AnyResult};
 use std::fmt::Display;
 use std::iter::Iterator;
-use std::slice::IterMut;
-use std::vec::Vec;
 #[derive(Copy, Clone, PartialEq, Debug)]
-pub(crate) struct Player {
+pub struct Player {
     symbol: PlayerSymbol,
     number: u8,
 }
@@ -24,10 +21,6 @@ impl Iterator for Player {
     }
 }
 
-pub(crate) trait PlayerIterator {
-    fn next_player(&mut self) -> &mut Player;
-}
-
 impl Display for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let symbol = match self.symbol {
@@ -39,19 +32,19 @@ impl Display for Player {
 }
 
 impl Player {
-    pub(crate) fn new(symbol: PlayerSymbol, number: u8) -> Self {
+    pub fn new(symbol: PlayerSymbol, number: u8) -> Self {
         Self { symbol, number }
     }
-    pub(crate) fn symbol(&self) -> PlayerSymbol {
+    pub fn symbol(&self) -> PlayerSymbol {
         self.symbol
     }
-    pub(crate) fn set_symbol(&mut self, symbol: PlayerSymbol) {
+    pub fn set_symbol(&mut self, symbol: PlayerSymbol) {
         self.symbol = symbol;
     }
-    pub(crate) fn current_player(&self) -> &Self {
+    pub fn current_player(&self) -> &Self {
         self
     }
-    pub(crate) fn get_player_number(&self) -> u8 {
+    pub fn get_player_number(&self) -> u8 {
         self.number
     }
 }
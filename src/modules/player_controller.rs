@@ -0,0 +1,66 @@
+use crate::modules::{
+    game::Game,
+    game_error::{GameError, GameResult},
+};
+
+use std::collections::VecDeque;
+
+/// What a controller decided to do with its turn
+#[derive(Clone, PartialEq, Debug)]
+pub enum PlayerAction {
+    /// Place the player's symbol at (x, y), 0-based
+    Move(u8, u8),
+    /// Take back the last move
+    Undo,
+    /// Play the last move taken back again
+    Redo,
+    /// Write the game to the given file
+    Save(String),
+    /// Replace the game with the one in the given file
+    Load(String),
+    /// Add the game to the end of the given game record file
+    Record(String),
+    /// Abandon the game
+    Quit,
+}
+
+/// Something that can pick moves for a player,
+/// e.g. a human at the terminal, an AI or a fixed script
+pub trait PlayerController {
+    /// Chooses what the current player of `game` does this turn.
+    /// The move is validated against the board by the caller.
+    fn choose_action(&mut self, game: &Game) -> GameResult<PlayerAction>;
+    /// Whether a person is making the choices.
+    /// Take-backs skip over turns that are not played by a person.
+    fn is_human(&self) -> bool {
+        false
+    }
+    /// Called by `Game::play_turn` once the game is over,
+    /// with the finished game
+    fn game_finished(&mut self, _game: &Game) -> GameResult<()> {
+        Ok(())
+    }
+}
+
+/// Plays a fixed list of moves in order,
+/// useful for replaying games and driving the game loop without a terminal
+pub struct ScriptedController {
+    moves: VecDeque<(u8, u8)>,
+}
+
+impl ScriptedController {
+    pub fn new(moves: Vec<(u8, u8)>) -> Self {
+        Self {
+            moves: moves.into(),
+        }
+    }
+}
+
+impl PlayerController for ScriptedController {
+    fn choose_action(&mut self, _game: &Game) -> GameResult<PlayerAction> {
+        match self.moves.pop_front() {
+            Some((x, y)) => Ok(PlayerAction::Move(x, y)),
+            None => Err(GameError::NoMovesLeft),
+        }
+    }
+}
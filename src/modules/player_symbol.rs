@@ -1,6 +1,8 @@
-use std::fmt::Display;
+use crate::modules::game_error::GameError;
+
+use std::{fmt::Display, str::FromStr};
 #[derive(Copy, Clone, PartialEq, Debug)]
-pub(crate) enum PlayerSymbol {
+pub enum PlayerSymbol {
     Cross,
     Nought,
 }
@@ -9,10 +11,11 @@ impl PlayerSymbol {
     /// Returns the opposite symbol of the current symbol
     /// E.g.
     /// ```
-    ///     Cross.opposite() == Nought
-    ///     Nought.opposite() == Cross
+    /// use x_and_o::PlayerSymbol::{Cross, Nought};
+    /// assert_eq!(Cross.opposite(), Nought);
+    /// assert_eq!(Nought.opposite(), Cross);
     /// ```
-    pub(crate) fn opposite(&self) -> Self {
+    pub fn opposite(&self) -> Self {
         match self {
             PlayerSymbol::Cross => PlayerSymbol::Nought,
             PlayerSymbol::Nought => PlayerSymbol::Cross,
@@ -29,3 +32,15 @@ impl Display for PlayerSymbol {
         write!(f, "{}", symbol)
     }
 }
+
+impl FromStr for PlayerSymbol {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "X" | "x" => Ok(PlayerSymbol::Cross),
+            "O" | "o" => Ok(PlayerSymbol::Nought),
+            _ => Err(GameError::Parse(format!("Unknown symbol: {}", s))),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::modules::{
+    board::Board,
+    game::Game,
+    game_error::{GameError, GameResult},
+    game_record::GameRecord,
+    move_record::MoveRecord,
+};
+
+/// Steps backwards and forwards through a recorded game
+pub struct Replay {
+    record: GameRecord,
+    /// The game at the move being viewed.
+    /// Moves after it are kept on the game's redo list.
+    game: Game,
+}
+
+impl Replay {
+    /// Checks the record and starts the replay before the first move
+    pub fn new(record: GameRecord) -> GameResult<Self> {
+        let mut game = record.to_game()?;
+        while game.undo().is_ok() {}
+        Ok(Self { record, game })
+    }
+    /// Gets the game being replayed
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+    /// Gets the board as it was after the move being viewed
+    pub fn board(&self) -> &Board {
+        self.game.board()
+    }
+    /// Gets how many moves have been played at this point, 0 at the start
+    pub fn ply(&self) -> usize {
+        self.game.history().len()
+    }
+    /// Gets how many moves the whole game has
+    pub fn move_count(&self) -> usize {
+        self.record.moves().len()
+    }
+    /// Gets the move that led to the position being viewed
+    pub fn last_move(&self) -> Option<MoveRecord> {
+        self.game.history().last().copied()
+    }
+    /// Steps one move forward
+    pub fn forward(&mut self) -> GameResult<MoveRecord> {
+        self.game.redo()
+    }
+    /// Steps one move back
+    pub fn back(&mut self) -> GameResult<MoveRecord> {
+        self.game.undo()
+    }
+    /// Jumps to the position after `ply` moves, 0 being the starting position
+    pub fn jump_to(&mut self, ply: usize) -> GameResult<()> {
+        if ply > self.move_count() {
+            return Err(GameError::NoSuchMove {
+                ply,
+                move_count: self.move_count(),
+            });
+        }
+        while self.ply() < ply {
+            self.forward()?;
+        }
+        while self.ply() > ply {
+            self.back()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::game_record::parse_records;
+
+    fn replay() -> Replay {
+        let records = parse_records("1. 2,2 1,2 2. 1,1 *\n").unwrap();
+        Replay::new(records[0].clone()).unwrap()
+    }
+
+    #[test]
+    fn jumps_to_any_move_of_the_game() {
+        let mut replay = replay();
+        assert_eq!(replay.ply(), 0);
+        replay.jump_to(3).unwrap();
+        assert_eq!(
+            replay.last_move().map(|record| record.coords()),
+            Some((0, 0))
+        );
+        replay.jump_to(1).unwrap();
+        assert_eq!(replay.board().empty_cells().len(), 8);
+        replay.jump_to(0).unwrap();
+        assert!(replay.last_move().is_none());
+    }
+
+    #[test]
+    fn rejects_a_move_past_the_end() {
+        let mut replay = replay();
+        replay.jump_to(2).unwrap();
+        assert!(matches!(
+            replay.jump_to(4),
+            Err(GameError::NoSuchMove {
+                ply: 4,
+                move_count: 3
+            })
+        ));
+        // Stays where it was
+        assert_eq!(replay.ply(), 2);
+    }
+}
@@ -0,0 +1,321 @@
+//! Saved games: a small line-based text format that records everything
+//! needed to carry on a game later, e.g.
+//!
+//! ```text
+//! # x-and-o saved game
+//! version 1
+//! mode single
+//! board 3 3 3
+//! players X O
+//! first 1
+//! current 2
+//! move X 1,1
+//! ```
+//!
+//! Board size is `width height win_length`, `first` and `current` are the
+//! 1-based numbers of the player who moved first and the player to move,
+//! and each `move` line is a symbol and 1-based x,y.
+//! Files without a `first` line are taken to have been started by player 1.
+//!
+//! A game set up from a position rather than an empty board also has
+//! a `position` line after `board`, in `Board::to_notation` format
+//! with the first player to move, e.g. `position X../.O./... x`.
+
+use crate::modules::{
+    board::Board,
+    game::Game,
+    game_error::{GameError, GameResult},
+    game_mode::GameMode,
+    move_record::parse_coords,
+    player_symbol::PlayerSymbol,
+};
+use std::{fs, path::Path};
+
+const SAVE_VERSION: u32 = 1;
+
+/// Writes `game` to the file at `path`, replacing anything already there
+pub fn save_game(game: &Game, path: impl AsRef<Path>) -> GameResult<()> {
+    fs::write(path, game_to_string(game))?;
+    Ok(())
+}
+
+/// Reads and checks a game saved with `save_game`
+pub fn load_game(path: impl AsRef<Path>) -> GameResult<Game> {
+    let text = fs::read_to_string(path)?;
+    game_from_str(&text)
+}
+
+/// Writes `game` in the saved game format
+pub fn game_to_string(game: &Game) -> String {
+    let board = game.board();
+    let symbols = game
+        .players()
+        .iter()
+        .map(|player| player.symbol().to_string())
+        .collect::<Vec<String>>();
+    let mut lines = vec![
+        "# x-and-o saved game".to_string(),
+        format!("version {}", SAVE_VERSION),
+        format!("mode {}", game.game_mode()),
+        format!(
+            "board {} {} {}",
+            board.width(),
+            board.height(),
+            board.win_length()
+        ),
+    ];
+    if let Some(position) = game.starting_position() {
+        lines.push(format!("position {}", position));
+    }
+    lines.extend([
+        format!("players {}", symbols.join(" ")),
+        format!("first {}", game.first_player_index() + 1),
+        format!("current {}", game.current_player_index() + 1),
+    ]);
+    for record in game.history() {
+        let (x, y) = record.coords();
+        lines.push(format!("move {} {},{}", record.player(), x + 1, y + 1));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Rebuilds a game from the saved game format,
+/// replaying every move so an impossible game is rejected
+pub fn game_from_str(text: &str) -> GameResult<Game> {
+    let mut version = None;
+    let mut mode = None;
+    let mut board = None;
+    let mut position = None;
+    let mut symbols = None;
+    let mut first = None;
+    let mut current = None;
+    let mut moves = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        let not_a_number = |_| GameError::Parse(format!("'{}' is not a number", value));
+        let parsed = match key {
+            "version" => value
+                .parse::<u32>()
+                .map(|value| version = Some(value))
+                .map_err(not_a_number),
+            "mode" => value.parse::<GameMode>().map(|value| mode = Some(value)),
+            "board" => parse_board(value).map(|value| board = Some(value)),
+            "position" => Board::from_notation(value).map(|value| position = Some(value)),
+            "players" => parse_symbols(value).map(|value| symbols = Some(value)),
+            "first" => value
+                .parse::<usize>()
+                .map(|value| first = Some(value))
+                .map_err(not_a_number),
+            "current" => value
+                .parse::<usize>()
+                .map(|value| current = Some(value))
+                .map_err(not_a_number),
+            "move" => parse_move(value).map(|value| moves.push((line_number, value))),
+            _ => Err(GameError::Parse(format!("Unknown entry '{}'", key))),
+        };
+        parsed.map_err(|e| e.at_line(line_number))?;
+    }
+    match version {
+        Some(SAVE_VERSION) => {}
+        Some(other) => {
+            return Err(GameError::Parse(format!(
+                "Unsupported save version {}",
+                other
+            )))
+        }
+        None => return Err(missing("version")),
+    }
+    let board = board.ok_or_else(|| missing("board"))?;
+    let to_move = match &position {
+        Some((start, to_move)) => {
+            let size = |board: &Board| (board.width(), board.height(), board.win_length());
+            if size(start) != size(&board) {
+                return Err(GameError::Parse(
+                    "The position isn't on a board of the saved size".to_string(),
+                ));
+            }
+            Some(*to_move)
+        }
+        None => None,
+    };
+    let mut game = Game::with_board(position.map_or(board, |(start, _)| start));
+    game.set_game_mode(mode.ok_or_else(|| missing("mode"))?);
+    let symbols = symbols.ok_or_else(|| missing("players"))?;
+    for (player, symbol) in game.players_mut().iter_mut().zip(symbols) {
+        player.set_symbol(symbol);
+    }
+    let first = first.unwrap_or(1);
+    if first == 0 {
+        return Err(GameError::InvalidSetup("There is no player 0".to_string()));
+    }
+    game.set_first_player(first - 1)?;
+    if to_move.is_some_and(|to_move| to_move != game.current_player_symbol()) {
+        return Err(GameError::Parse(format!(
+            "Player {} moves first but the position has the other side to move",
+            first
+        )));
+    }
+
+    for (line_number, (symbol, x, y)) in moves {
+        game.apply_move_for(symbol, x, y)
+            .map_err(|e| e.at_line(line_number))?;
+    }
+
+    let current = current.ok_or_else(|| missing("current"))?;
+    if current != game.current_player_index() + 1 {
+        return Err(GameError::Parse(format!(
+            "The file says it is player {}'s turn but after its moves it is player {}'s",
+            current,
+            game.current_player_index() + 1
+        )));
+    }
+    Ok(game)
+}
+
+/// Error for a saved game without the `entry` line
+fn missing(entry: &str) -> GameError {
+    GameError::Parse(format!("Missing {}", entry))
+}
+
+/// Parses `width height win_length`
+fn parse_board(value: &str) -> GameResult<Board> {
+    let sizes = value
+        .split_whitespace()
+        .map(|size| size.parse::<u8>())
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| GameError::Parse("Board sizes must be numbers from 1 to 255".to_string()))?;
+    match sizes[..] {
+        [width, height, win_length] => Board::with_size(width, height, win_length),
+        _ => Err(GameError::Parse(
+            "Expected a width, height and win length".to_string(),
+        )),
+    }
+}
+
+/// Parses one symbol per player, e.g. `X O`
+fn parse_symbols(value: &str) -> GameResult<[PlayerSymbol; 2]> {
+    let symbols = value
+        .split_whitespace()
+        .map(|symbol| symbol.parse::<PlayerSymbol>())
+        .collect::<GameResult<Vec<PlayerSymbol>>>()?;
+    match symbols[..] {
+        [first, second] if first != second => Ok([first, second]),
+        [_, _] => Err(GameError::InvalidSetup(
+            "Both players have the same symbol".to_string(),
+        )),
+        _ => Err(GameError::Parse(
+            "Expected a symbol for each of the 2 players".to_string(),
+        )),
+    }
+}
+
+/// Parses a symbol and 1-based coordinates, e.g. `X 1,2`
+fn parse_move(value: &str) -> GameResult<(PlayerSymbol, u8, u8)> {
+    let (symbol, coords) = value.split_once(' ').ok_or_else(|| {
+        GameError::Parse("Expected a symbol and coordinates, e.g. X 1,2".to_string())
+    })?;
+    let symbol = symbol.parse::<PlayerSymbol>()?;
+    let (x, y) = parse_coords(coords)?;
+    Ok((symbol, x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAVED: &str = "\
+# x-and-o saved game
+version 1
+mode single
+board 3 3 3
+players X O
+first 1
+current 2
+move X 2,2
+move O 1,1
+move X 3,1
+";
+
+    /// Gets the saved game with `from` replaced by `to`
+    fn edited(from: &str, to: &str) -> String {
+        assert!(SAVED.contains(from));
+        SAVED.replace(from, to)
+    }
+
+    #[test]
+    fn round_trips_a_game() {
+        let game = game_from_str(SAVED).unwrap();
+        assert_eq!(game.history().len(), 3);
+        assert_eq!(game.current_player_index(), 1);
+        assert_eq!(game_to_string(&game), SAVED);
+    }
+
+    #[test]
+    fn round_trips_a_game_set_up_from_a_position() {
+        let mut game = Game::from_notation("X../.O./... x").unwrap();
+        game.apply_move(2, 0).unwrap();
+        let text = game_to_string(&game);
+        assert!(text.contains("position X../.O./... x\n"));
+        let loaded = game_from_str(&text).unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.history().len(), 1);
+        assert_eq!(game_to_string(&loaded), text);
+    }
+
+    #[test]
+    fn rejects_a_position_that_does_not_fit_the_game() {
+        let wrong_size = edited(
+            "board 3 3 3\n",
+            "board 3 3 3\nposition ..../..../..../.... x\n",
+        );
+        assert!(game_from_str(&wrong_size).is_err());
+        // Player 1 is X, but the position has O to move
+        let wrong_first = edited("board 3 3 3\n", "board 3 3 3\nposition ..X/.../... o\n");
+        assert!(game_from_str(&wrong_first).is_err());
+    }
+
+    #[test]
+    fn rejects_a_missing_or_unknown_version() {
+        assert!(game_from_str(&edited("version 1\n", "")).is_err());
+        assert!(game_from_str(&edited("version 1", "version 2")).is_err());
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let taken = edited("move X 3,1", "move X 1,1");
+        match game_from_str(&taken) {
+            Err(e) => assert!(matches!(e.root(), GameError::CellOccupied { x: 0, y: 0 })),
+            Ok(_) => panic!("a move on a taken cell was accepted"),
+        }
+        let off_board = edited("move X 3,1", "move X 4,1");
+        assert!(game_from_str(&off_board).is_err());
+        let out_of_turn = edited("move X 3,1", "move O 3,1");
+        assert!(game_from_str(&out_of_turn).is_err());
+    }
+
+    #[test]
+    fn rejects_a_turn_that_does_not_match_the_moves() {
+        assert!(game_from_str(&edited("current 2", "current 1")).is_err());
+    }
+
+    #[test]
+    fn rejects_impossible_setups() {
+        assert!(game_from_str(&edited("players X O", "players X X")).is_err());
+        assert!(game_from_str(&edited("board 3 3 3", "board 3 3 4")).is_err());
+        assert!(game_from_str(&edited("first 1", "first 0")).is_err());
+        assert!(game_from_str(&edited("mode single", "mode solo")).is_err());
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_entry() {
+        let result = game_from_str(&edited("move X 3,1", "move X 3;1"));
+        assert!(matches!(result, Err(GameError::AtLine { line: 10, .. })));
+    }
+}
@@ -0,0 +1,247 @@
+//! Solves small boards completely: every position that can come up in a game
+//! starting from an empty board, with what perfect play leads to from there.
+//!
+//! The table can be written out one position per line, sorted by notation:
+//!
+//! ```text
+//! .../.OO/X.X x win 1
+//! ```
+//!
+//! That is the position in `Board::to_notation` format, the result for the
+//! side to move (`win`, `draw` or `loss`) and how many more moves the game
+//! lasts when both sides play perfectly, the winner finishing as fast as
+//! they can and the loser holding out as long as they can.
+
+use crate::modules::{
+    board::Board, game_error::GameResult, game_status::GameStatus, player::Player,
+    player_symbol::PlayerSymbol,
+};
+
+use std::{collections::BTreeMap, fmt::Display, fs, path::Path};
+
+/// Number of positions reachable on the standard 3x3 board, counting the
+/// empty board and finished games, but not positions after a game has ended
+pub const STANDARD_POSITIONS: usize = 5478;
+
+/// Number of different ways a game on the standard 3x3 board can be played out
+pub const STANDARD_GAMES: u64 = 255_168;
+
+/// What a position is worth to the player whose turn it is
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// Gets what the same result is worth to the other player
+    pub fn opposite(&self) -> Self {
+        match self {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss => Outcome::Win,
+        }
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Outcome::Win => "win",
+            Outcome::Draw => "draw",
+            Outcome::Loss => "loss",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The value of a position under perfect play
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Solution {
+    /// The result for the player to move
+    pub outcome: Outcome,
+    /// Moves left until the game ends
+    pub distance: u8,
+}
+
+impl Solution {
+    /// Ranks solutions for the player to move, higher is better:
+    /// the quickest win, then a draw, then the slowest loss
+    fn rank(&self) -> i32 {
+        let distance = self.distance as i32;
+        match self.outcome {
+            Outcome::Win => 1000 - distance,
+            Outcome::Draw => 0,
+            Outcome::Loss => -1000 + distance,
+        }
+    }
+}
+
+/// A solved position and how many ways the game can go on from it
+#[derive(Copy, Clone, Debug)]
+struct Entry {
+    solution: Solution,
+    games: u64,
+}
+
+/// Every position reachable from an empty board, solved
+pub struct SolutionTable {
+    /// Keyed by `Board::to_notation`
+    entries: BTreeMap<String, Entry>,
+    /// Ways the game can be played out from the empty board
+    games: u64,
+}
+
+impl SolutionTable {
+    /// Solves every position reachable from an empty board
+    /// the size of `board`, with X moving first.
+    /// Only small boards can be solved in reasonable time.
+    pub fn solve(board: &Board) -> Self {
+        let mut table = Self {
+            entries: BTreeMap::new(),
+            games: 0,
+        };
+        let mut board = board.empty_like();
+        let cross = Player::new(PlayerSymbol::Cross, 1);
+        let nought = Player::new(PlayerSymbol::Nought, 2);
+        table.games = table.visit(&mut board, cross, nought).games;
+        table
+    }
+    /// Gets the number of positions in the table
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Checks whether the table has no positions, which never happens once solved
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Gets the number of different ways a game can be played out
+    pub fn games(&self) -> u64 {
+        self.games
+    }
+    /// Checks the position and game counts against the known ones for 3x3
+    pub fn matches_standard_counts(&self) -> bool {
+        self.len() == STANDARD_POSITIONS && self.games == STANDARD_GAMES
+    }
+    /// Gets the solution for `board` with `to_move` to play,
+    /// or None if that position can't come up in a game
+    pub fn get(&self, board: &Board, to_move: PlayerSymbol) -> Option<Solution> {
+        self.entries
+            .get(&board.to_notation(to_move))
+            .map(|entry| entry.solution)
+    }
+    /// Gets every move that keeps the best result for `to_move`,
+    /// finishing a win fastest or holding out a loss longest
+    pub fn best_moves(&self, board: &Board, to_move: Player) -> Vec<(u8, u8)> {
+        let mut best_rank = i32::MIN;
+        let mut best_moves = Vec::new();
+        for (x, y) in board.empty_cells() {
+            let mut child = board.clone();
+            if child.set(x, y, to_move).is_err() {
+                continue;
+            }
+            let solution = match self.get(&child, to_move.symbol().opposite()) {
+                Some(solution) => solution,
+                None => continue,
+            };
+            let rank = Solution {
+                outcome: solution.outcome.opposite(),
+                distance: solution.distance + 1,
+            }
+            .rank();
+            if rank > best_rank {
+                best_rank = rank;
+                best_moves.clear();
+            }
+            if rank == best_rank {
+                best_moves.push((x, y));
+            }
+        }
+        best_moves
+    }
+    /// Writes the table to `path`, replacing anything already there
+    pub fn export(&self, path: impl AsRef<Path>) -> GameResult<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    /// Solves `board` with `player` to move, and everything after it
+    fn visit(&mut self, board: &mut Board, player: Player, opponent: Player) -> Entry {
+        let key = board.to_notation(player.symbol());
+        if let Some(entry) = self.entries.get(&key) {
+            return *entry;
+        }
+        let entry = match board.status() {
+            // Only the player who just moved can have won
+            GameStatus::Won { .. } => Entry {
+                solution: Solution {
+                    outcome: Outcome::Loss,
+                    distance: 0,
+                },
+                games: 1,
+            },
+            GameStatus::Draw => Entry {
+                solution: Solution {
+                    outcome: Outcome::Draw,
+                    distance: 0,
+                },
+                games: 1,
+            },
+            GameStatus::InProgress => {
+                let mut best: Option<Solution> = None;
+                let mut games = 0;
+                for (x, y) in board.empty_cells() {
+                    board
+                        .set(x, y, player)
+                        .expect("the solver only plays on empty cells");
+                    let child = self.visit(board, opponent, player);
+                    board
+                        .clear(x, y)
+                        .expect("the solver only clears cells it played on");
+                    games += child.games;
+                    let solution = Solution {
+                        outcome: child.solution.outcome.opposite(),
+                        distance: child.solution.distance + 1,
+                    };
+                    if best.is_none_or(|best| solution.rank() > best.rank()) {
+                        best = Some(solution);
+                    }
+                }
+                Entry {
+                    solution: best.expect("a game in progress has an empty cell"),
+                    games,
+                }
+            }
+        };
+        self.entries.insert(key, entry);
+        entry
+    }
+}
+
+impl Display for SolutionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (notation, entry) in &self.entries {
+            writeln!(
+                f,
+                "{} {} {}",
+                notation, entry.solution.outcome, entry.solution.distance
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_every_standard_position_and_game() {
+        let table = SolutionTable::solve(&Board::new());
+        assert!(table.matches_standard_counts());
+        let empty = table.get(&Board::new(), PlayerSymbol::Cross).unwrap();
+        assert_eq!(empty.outcome, Outcome::Draw);
+        assert_eq!(empty.distance, 9);
+    }
+}
@@ -0,0 +1,54 @@
+//! Files that engines which learn keep what they know in,
+//! as the text their `Display` writes and their `FromStr` reads
+
+use crate::modules::{
+    board::Board,
+    game_error::{GameError, GameResult},
+    player_symbol::PlayerSymbol,
+};
+
+use std::{fmt::Display, fs, io, path::Path, str::FromStr};
+
+/// Reads state written by `save`
+pub(crate) fn load<T: FromStr<Err = GameError>>(path: impl AsRef<Path>) -> GameResult<T> {
+    fs::read_to_string(path)?.parse()
+}
+
+/// Reads state written by `save`, or starts from nothing
+/// if the file doesn't exist yet
+pub(crate) fn load_or_default<T: FromStr<Err = GameError> + Default>(
+    path: impl AsRef<Path>,
+) -> GameResult<T> {
+    match fs::read_to_string(path) {
+        Ok(text) => text.parse(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads a position a state file keys something by, which has to be
+/// an unfinished game written the way `Board::canonical` turns it,
+/// as nothing else is ever looked up
+pub(crate) fn canonical_position(notation: &str) -> GameResult<(Board, PlayerSymbol)> {
+    let (board, to_move) = Board::from_notation(notation)?;
+    if board.is_game_over() {
+        return Err(GameError::Parse(format!(
+            "'{}' is a finished game",
+            notation
+        )));
+    }
+    let canonical = board.canonical().0.to_notation(to_move);
+    if canonical != notation {
+        return Err(GameError::Parse(format!(
+            "'{}' should be written as '{}', its canonical form",
+            notation, canonical
+        )));
+    }
+    Ok((board, to_move))
+}
+
+/// Writes `state` to `path`, replacing anything already there
+pub(crate) fn save(state: &impl Display, path: impl AsRef<Path>) -> GameResult<()> {
+    fs::write(path, state.to_string())?;
+    Ok(())
+}
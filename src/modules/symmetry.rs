@@ -0,0 +1,66 @@
+/// One of the eight ways to turn or flip a board onto itself.
+/// Turns are clockwise, with y counting down from the top row.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Symmetry {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    /// Mirror left to right
+    FlipHorizontal,
+    /// Mirror top to bottom
+    FlipVertical,
+    /// Mirror along the diagonal from the top left corner
+    FlipDiagonal,
+    /// Mirror along the diagonal from the top right corner
+    FlipAntiDiagonal,
+}
+
+impl Symmetry {
+    /// Every symmetry, starting with the identity
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::FlipDiagonal,
+        Symmetry::FlipAntiDiagonal,
+    ];
+
+    /// Checks whether this symmetry turns a `width` by `height` board
+    /// into a `height` by `width` one
+    pub fn swaps_sides(&self) -> bool {
+        matches!(
+            self,
+            Symmetry::Rotate90
+                | Symmetry::Rotate270
+                | Symmetry::FlipDiagonal
+                | Symmetry::FlipAntiDiagonal
+        )
+    }
+    /// Gets the symmetry that undoes this one
+    pub fn inverse(&self) -> Self {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => *other,
+        }
+    }
+    /// Maps the cell (x, y) of a `width` by `height` board
+    /// to where it ends up once the board is turned or flipped
+    pub fn apply(&self, x: u8, y: u8, width: u8, height: u8) -> (u8, u8) {
+        let (right, bottom) = (width - 1, height - 1);
+        match self {
+            Symmetry::Identity => (x, y),
+            Symmetry::Rotate90 => (bottom - y, x),
+            Symmetry::Rotate180 => (right - x, bottom - y),
+            Symmetry::Rotate270 => (y, right - x),
+            Symmetry::FlipHorizontal => (right - x, y),
+            Symmetry::FlipVertical => (x, bottom - y),
+            Symmetry::FlipDiagonal => (y, x),
+            Symmetry::FlipAntiDiagonal => (bottom - y, right - x),
+        }
+    }
+}
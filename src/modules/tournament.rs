@@ -0,0 +1,374 @@
+//! Round-robin tournaments between engines, played without any output,
+//! with a crosstable and Elo ratings at the end.
+//!
+//! Every pair of engines plays the same number of games, taking turns to
+//! be X, who moves first. Each engine is built afresh for every game with
+//! its own `Rng` drawn from the tournament seed, so a tournament with the
+//! same engines and seed always gives the same results.
+
+use crate::modules::{
+    board::Board, engine::AiController, engine::Engine, game::Game, game_error::GameResult,
+    game_status::GameStatus, random::Rng,
+};
+
+use std::fmt::Display;
+
+/// Builds an engine for a game on `board`, using `rng` for any random choices
+pub type EngineFactory = Box<dyn Fn(&Board, Rng) -> Box<dyn Engine>>;
+
+/// z-score for a 95% confidence interval
+const CONFIDENCE_Z: f64 = 1.96;
+
+/// Average rating the ratings are centred on
+const AVERAGE_RATING: f64 = 1500.0;
+
+/// An engine taking part in a tournament
+struct Entrant {
+    name: String,
+    factory: EngineFactory,
+}
+
+/// Plays every registered engine against every other one
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    /// Empty board each game starts from
+    board: Board,
+    games_per_pairing: u32,
+    seed: u64,
+}
+
+impl Tournament {
+    /// Creates a tournament on the standard 3x3 board,
+    /// with two games per pairing so each engine is X once
+    pub fn new(seed: u64) -> Self {
+        Self {
+            entrants: Vec::new(),
+            board: Board::new(),
+            games_per_pairing: 2,
+            seed,
+        }
+    }
+    /// Plays on an empty board the size of `board` instead
+    pub fn with_board(mut self, board: &Board) -> Self {
+        self.board = board.empty_like();
+        self
+    }
+    /// Sets how many games each pair of engines plays.
+    /// An odd number gives the engine registered first the extra game as X.
+    pub fn with_games_per_pairing(mut self, games: u32) -> Self {
+        self.games_per_pairing = games;
+        self
+    }
+    /// Adds an engine to the tournament under `name`
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&Board, Rng) -> Box<dyn Engine> + 'static,
+    ) {
+        self.entrants.push(Entrant {
+            name: name.to_string(),
+            factory: Box::new(factory),
+        });
+    }
+    /// Plays every game and collects the results
+    pub fn run(&self) -> GameResult<Crosstable> {
+        let count = self.entrants.len();
+        let mut results = vec![vec![PairResult::default(); count]; count];
+        let mut rng = Rng::new(self.seed);
+        for first in 0..count {
+            for second in first + 1..count {
+                for game_number in 0..self.games_per_pairing {
+                    // Swap colours every game
+                    let (cross, nought) = if game_number % 2 == 0 {
+                        (first, second)
+                    } else {
+                        (second, first)
+                    };
+                    let (winner, loser) = match self.play(cross, nought, &mut rng)? {
+                        Some(0) => (cross, nought),
+                        Some(_) => (nought, cross),
+                        None => {
+                            results[cross][nought].draws += 1;
+                            results[nought][cross].draws += 1;
+                            continue;
+                        }
+                    };
+                    results[winner][loser].wins += 1;
+                    results[loser][winner].losses += 1;
+                }
+            }
+        }
+        Ok(Crosstable {
+            names: self.entrants.iter().map(|e| e.name.clone()).collect(),
+            results,
+        })
+    }
+    /// Plays one game with entrant `cross` as X against `nought`,
+    /// returning the index of the winning player, or None for a draw
+    fn play(&self, cross: usize, nought: usize, rng: &mut Rng) -> GameResult<Option<usize>> {
+        let mut game = Game::with_board(self.board.clone());
+        for (index, entrant) in [cross, nought].into_iter().enumerate() {
+            let engine = (self.entrants[entrant].factory)(&self.board, Rng::new(rng.next_u64()));
+            game.set_controller(index, Box::new(AiController::new(engine)))?;
+        }
+        while !game.game_over() {
+            game.play_turn()?;
+        }
+        Ok(match game.status() {
+            GameStatus::Won { winner, .. } => game.players().iter().position(|p| p == winner),
+            _ => None,
+        })
+    }
+}
+
+/// How one engine did against another
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct PairResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl PairResult {
+    /// Gets the number of games played
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+    /// Gets the score, with a point for a win and half for a draw
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+}
+
+impl Display for PairResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}-{}-{}", self.wins, self.draws, self.losses)
+    }
+}
+
+/// An Elo rating and its 95% confidence interval
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Rating {
+    pub elo: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+/// Results of a tournament: how every engine did against every other one
+#[derive(Clone, PartialEq, Debug)]
+pub struct Crosstable {
+    names: Vec<String>,
+    /// `results[a][b]` is how engine `a` did against engine `b`
+    results: Vec<Vec<PairResult>>,
+}
+
+impl Crosstable {
+    /// Gets the engines' names in the order they were registered
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+    /// Gets how engine `engine` did against engine `opponent`
+    pub fn result(&self, engine: usize, opponent: usize) -> PairResult {
+        self.results[engine][opponent]
+    }
+    /// Gets how engine `engine` did over the whole tournament
+    pub fn total(&self, engine: usize) -> PairResult {
+        self.results[engine]
+            .iter()
+            .fold(PairResult::default(), |total, result| PairResult {
+                wins: total.wins + result.wins,
+                draws: total.draws + result.draws,
+                losses: total.losses + result.losses,
+            })
+    }
+    /// Works out an Elo rating for every engine, averaging 1500.
+    ///
+    /// Each rating is the engine's performance rating: the average rating
+    /// of the engines it played plus the Elo difference its score implies.
+    /// The confidence interval comes from the spread of its game scores,
+    /// widened like a Wilson interval so it never has no width.
+    /// A perfect or zero score is counted as half a point off,
+    /// so that it still gives a finite rating, and the interval reaches
+    /// past it to within a quarter of a point of all or nothing.
+    pub fn ratings(&self) -> Vec<Rating> {
+        let count = self.names.len();
+        let totals = (0..count).map(|e| self.total(e)).collect::<Vec<_>>();
+        let mut elos = vec![AVERAGE_RATING; count];
+        // The ratings depend on each other, so repeat until they settle.
+        // Each step only goes halfway, or two engines would just swap ratings.
+        for _ in 0..100 {
+            let new_elos = (0..count)
+                .map(|engine| {
+                    let target =
+                        self.opponent_average(engine, &elos) + performance(&totals[engine]);
+                    (elos[engine] + target) / 2.0
+                })
+                .collect::<Vec<f64>>();
+            let shift = AVERAGE_RATING - new_elos.iter().sum::<f64>() / count.max(1) as f64;
+            elos = new_elos.iter().map(|elo| elo + shift).collect();
+        }
+        (0..count)
+            .map(|engine| {
+                let total = totals[engine];
+                let base = elos[engine] - performance(&total);
+                let (low, high) = score_interval(&total);
+                Rating {
+                    elo: elos[engine],
+                    low: base + elo_from_score(low),
+                    high: base + elo_from_score(high),
+                }
+            })
+            .collect()
+    }
+    /// Gets the average rating of the opponents `engine` played,
+    /// counting each one once per game
+    fn opponent_average(&self, engine: usize, elos: &[f64]) -> f64 {
+        let mut games = 0;
+        let mut sum = 0.0;
+        for (opponent, result) in self.results[engine].iter().enumerate() {
+            games += result.games();
+            sum += elos[opponent] * result.games() as f64;
+        }
+        if games == 0 {
+            AVERAGE_RATING
+        } else {
+            sum / games as f64
+        }
+    }
+}
+
+impl Display for Crosstable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name_width = self.names.iter().map(|name| name.len()).max().unwrap_or(0);
+        let cell_width = (0..self.names.len())
+            .flat_map(|row| {
+                self.results[row]
+                    .iter()
+                    .map(|result| result.to_string().len())
+            })
+            .max()
+            .unwrap_or(0)
+            .max(5);
+        // Header: a column per opponent, numbered like the rows
+        write!(f, "{:>4}{:2$}", "", "", name_width + 1)?;
+        for column in 0..self.names.len() {
+            write!(f, " {:>1$}", column + 1, cell_width)?;
+        }
+        writeln!(f, " {:>8} {:>6}  95% interval", "Score", "Elo")?;
+        let ratings = self.ratings();
+        for (row, name) in self.names.iter().enumerate() {
+            write!(f, "{:>3}. {:2$}", row + 1, name, name_width + 1)?;
+            for column in 0..self.names.len() {
+                if row == column {
+                    write!(f, " {:>1$}", "-", cell_width)?;
+                } else {
+                    write!(f, " {:>1$}", self.results[row][column], cell_width)?;
+                }
+            }
+            let total = self.total(row);
+            let rating = ratings[row];
+            writeln!(
+                f,
+                " {:>8} {:>6.0}  {:.0} to {:.0}",
+                format!("{}/{}", total.points(), total.games()),
+                rating.elo,
+                rating.low,
+                rating.high
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Gets the Elo difference implied by `result`'s score
+fn performance(result: &PairResult) -> f64 {
+    if result.games() == 0 {
+        return 0.0;
+    }
+    elo_difference(result.points() / result.games() as f64, result.games())
+}
+
+/// Gets the Elo difference that makes the expected score `score`,
+/// keeping the score half a point away from all or nothing in `games` games
+fn elo_difference(score: f64, games: u32) -> f64 {
+    let margin = 0.5 / games.max(1) as f64;
+    elo_from_score(score.clamp(margin, 1.0 - margin))
+}
+
+/// Gets the Elo difference that makes the expected score `score`,
+/// which has to be strictly between 0 and 1
+fn elo_from_score(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// Gets the 95% confidence interval of the average score per game
+/// in `result`, kept a quarter of a point away from all or nothing so its
+/// ends have finite ratings either side of the one `elo_difference` gives
+fn score_interval(result: &PairResult) -> (f64, f64) {
+    let games = result.games().max(1) as f64;
+    let mean = result.points() / games;
+    let variance = (result.wins as f64 * (1.0 - mean).powi(2)
+        + result.draws as f64 * (0.5 - mean).powi(2)
+        + result.losses as f64 * mean.powi(2))
+        / games;
+    // Wilson's centre and width, with the spread of the scores
+    // in place of the spread of wins and losses
+    let z_squared = CONFIDENCE_Z * CONFIDENCE_Z;
+    let scale = 1.0 + z_squared / games;
+    let centre = (mean + z_squared / (2.0 * games)) / scale;
+    let error =
+        CONFIDENCE_Z / scale * (variance / games + z_squared / (4.0 * games * games)).sqrt();
+    let margin = 0.25 / games;
+    (
+        (centre - error).clamp(margin, 1.0 - margin),
+        (centre + error).clamp(margin, 1.0 - margin),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::{greedy::GreedyEngine, mcts::MctsEngine};
+
+    #[test]
+    fn intervals_always_have_the_rating_inside() {
+        for (wins, draws, losses) in [
+            (6, 0, 0),
+            (5, 1, 0),
+            (0, 1, 5),
+            (0, 0, 6),
+            (0, 6, 0),
+            (2, 2, 2),
+        ] {
+            let result = PairResult {
+                wins,
+                draws,
+                losses,
+            };
+            let estimate = performance(&result);
+            let (low, high) = score_interval(&result);
+            let (low, high) = (elo_from_score(low), elo_from_score(high));
+            assert!(
+                low < estimate && estimate < high,
+                "{}: {} to {} around {}",
+                result,
+                low,
+                high,
+                estimate
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_crosstable() {
+        let run = || {
+            let mut tournament = Tournament::new(7).with_games_per_pairing(4);
+            tournament.register("greedy", |_, _| Box::new(GreedyEngine::new()));
+            tournament.register("mcts", |_, rng| {
+                Box::new(MctsEngine::new(rng).with_iterations(50))
+            });
+            tournament.run().unwrap()
+        };
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,117 @@
+/// How a stored score relates to the position's real score,
+/// which depends on the alpha-beta window it was searched with
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Bound {
+    /// The score is the real score
+    Exact,
+    /// A move was found scoring at least this, cutting the search short
+    Lower,
+    /// No move scored more than this
+    Upper,
+}
+
+/// What a search found out about one position
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TableEntry {
+    /// `Board::position_hash` of the position
+    pub key: u64,
+    /// How many moves ahead the position was searched
+    pub depth: i32,
+    pub score: i32,
+    pub bound: Bound,
+    /// The best move found, tried first when the position comes up again
+    pub best_move: Option<(u8, u8)>,
+}
+
+/// Fixed-size store of searched positions, looked up by hash,
+/// so a position reached by different move orders is only searched once.
+/// When two positions share a slot, the one searched deeper is kept.
+pub struct TranspositionTable {
+    entries: Vec<Option<TableEntry>>,
+}
+
+impl TranspositionTable {
+    /// Creates a table with room for `entries` positions,
+    /// rounded up to a power of two
+    pub fn new(entries: usize) -> Self {
+        Self {
+            entries: vec![None; entries.max(1).next_power_of_two()],
+        }
+    }
+    /// Gets the number of slots in the table
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+    /// Gets what is stored for the position with hash `key`
+    pub fn get(&self, key: u64) -> Option<&TableEntry> {
+        self.entries[self.slot(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+    /// Stores `entry`, unless its slot holds a different position
+    /// that was searched deeper
+    pub fn store(&mut self, entry: TableEntry) {
+        let slot = self.slot(entry.key);
+        let keep_old = self.entries[slot]
+            .as_ref()
+            .is_some_and(|old| old.key != entry.key && old.depth > entry.depth);
+        if !keep_old {
+            self.entries[slot] = Some(entry);
+        }
+    }
+    /// Forgets every position
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        // The length is a power of two, so this is `key % len`
+        key as usize & (self.entries.len() - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, depth: i32) -> TableEntry {
+        TableEntry {
+            key,
+            depth,
+            score: 0,
+            bound: Bound::Exact,
+            best_move: None,
+        }
+    }
+
+    #[test]
+    fn rounds_the_size_up_to_a_power_of_two() {
+        assert_eq!(TranspositionTable::new(0).capacity(), 1);
+        assert_eq!(TranspositionTable::new(100).capacity(), 128);
+    }
+
+    #[test]
+    fn finds_only_the_position_stored() {
+        let mut table = TranspositionTable::new(16);
+        table.store(entry(5, 2));
+        assert_eq!(table.get(5), Some(&entry(5, 2)));
+        // Same slot, different position
+        assert_eq!(table.get(5 + 16), None);
+        table.clear();
+        assert_eq!(table.get(5), None);
+    }
+
+    #[test]
+    fn keeps_the_deeper_of_two_positions_sharing_a_slot() {
+        let mut table = TranspositionTable::new(16);
+        table.store(entry(5, 4));
+        table.store(entry(5 + 16, 2));
+        assert_eq!(table.get(5), Some(&entry(5, 4)));
+        assert_eq!(table.get(5 + 16), None);
+        // A new search of the same position always replaces the old one
+        table.store(entry(5, 1));
+        assert_eq!(table.get(5), Some(&entry(5, 1)));
+        table.store(entry(5 + 16, 3));
+        assert_eq!(table.get(5 + 16), Some(&entry(5 + 16, 3)));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::modules::{board::Board, game_error::GameError};
+
+use std::{fmt::Display, str::FromStr};
+
+/// Named board sizes and win lengths
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Variant {
+    /// Noughts and crosses on 3x3, three in a row
+    Standard,
+    /// Five in a row on 15x15
+    Gomoku,
+}
+
+impl Variant {
+    /// Gets the `(width, height, win_length)` this variant is played with
+    pub fn size(&self) -> (u8, u8, u8) {
+        match self {
+            Variant::Standard => (3, 3, 3),
+            Variant::Gomoku => (15, 15, 5),
+        }
+    }
+    /// Creates an empty board for this variant
+    pub fn board(&self) -> Board {
+        let (width, height, win_length) = self.size();
+        Board::with_size(width, height, win_length).expect("variant sizes are valid")
+    }
+    /// Finds the variant played on `board`, if it is one of the named ones
+    pub fn of(board: &Board) -> Option<Variant> {
+        let size = (board.width(), board.height(), board.win_length());
+        [Variant::Standard, Variant::Gomoku]
+            .into_iter()
+            .find(|variant| variant.size() == size)
+    }
+}
+
+impl Display for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Variant::Standard => "standard",
+            Variant::Gomoku => "gomoku",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Variant {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "standard" => Ok(Variant::Standard),
+            "gomoku" => Ok(Variant::Gomoku),
+            _ => Err(GameError::Parse(format!("Unknown variant: {}", s))),
+        }
+    }
+}